@@ -1,11 +1,235 @@
 use crate::params::ext::GenerationParametersExt;
 use crate::params::VecSeeds;
+use anyhow::anyhow;
 pub use generator_core::algorithm::*;
-use generator_core::params::GenerationParameters;
+use generator_core::params::{EdgeModel, GenerationParameters, GraphKind, SeedEnum};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use tracing::info;
 
-pub fn generate_parameters(params: &mut GenerationParameters<VecSeeds>) {
+pub fn generate_parameters<M: EdgeModel>(params: &mut GenerationParameters<VecSeeds, M>) {
     info!("Computing W...");
     params.w = params.compute_weights().into_iter().sum();
     info!("Computed W = {}", params.w);
 }
+
+/// Number of node pairs [calibrate_scaling] uses to estimate the expected average degree.
+const CALIBRATION_SAMPLES: u64 = 100_000;
+
+/// A node pair used to estimate the expected average degree.
+struct PairSample {
+    /// Weight of node i.
+    w_i: f32,
+    /// Weight of node j.
+    w_j: f32,
+    /// Position of node i.
+    p_i: Vec<f32>,
+    /// Position of node j.
+    p_j: Vec<f32>,
+}
+
+/// Picks the node pairs used to estimate the expected average degree.
+///
+/// All candidate pairs are used when there are at most `samples` of them, otherwise `samples` pairs are drawn uniformly.
+/// The pairs only depend on the seeds of the graph, such that every shard arrives at the same estimate.
+///
+/// Returns the pairs and the factor that turns their mean edge probability into the expected average degree.
+fn sample_pairs<M: EdgeModel>(
+    params: &GenerationParameters<VecSeeds, M>,
+    samples: u64,
+) -> (Vec<PairSample>, f64) {
+    let v = params.v;
+    let undirected = params.graph_kind == GraphKind::Undirected;
+    // Saturating, so huge graphs are always sampled.
+    let (candidates, factor) = if undirected {
+        (
+            v.saturating_mul(v.saturating_sub(1)) / 2,
+            v.saturating_sub(1) as f64,
+        )
+    } else {
+        (v.saturating_mul(v), v as f64)
+    };
+
+    let pairs: Vec<(u64, u64)> = if candidates <= samples {
+        (0..v)
+            .flat_map(|j| (0..v).map(move |i| (i, j)))
+            .filter(|(i, j)| params.is_candidate_pair(*i, *j))
+            .collect()
+    } else {
+        let mut rng = StdRng::seed_from_u64(params.get_seed(SeedEnum::Edge));
+        (0..samples)
+            .map(|_| loop {
+                let (i, j) = (rng.gen_range(0..v), rng.gen_range(0..v));
+                if !undirected {
+                    break (i, j);
+                } else if i != j {
+                    break (i.min(j), i.max(j));
+                }
+            })
+            .collect()
+    };
+
+    let pairs = pairs
+        .into_iter()
+        .map(|(i, j)| PairSample {
+            w_i: params.compute_weight(i),
+            w_j: params.compute_weight(j),
+            p_i: params.compute_position(i),
+            p_j: params.compute_position(j),
+        })
+        .collect();
+
+    (pairs, factor)
+}
+
+/// Expected average degree of a graph, estimated from the given node pairs. See [sample_pairs].
+fn estimate_average_degree<M: EdgeModel>(
+    params: &GenerationParameters<VecSeeds, M>,
+    pairs: &[PairSample],
+    factor: f64,
+) -> f64 {
+    let sum: f64 = pairs
+        .iter()
+        .map(|s| {
+            params
+                .model
+                .probability(s.w_i, s.w_j, &s.p_i, &s.p_j, params) as f64
+        })
+        .sum();
+    factor * sum / (pairs.len().max(1) as f64)
+}
+
+/// Estimates the expected average degree of the graph.
+///
+/// For directed graphs this is the average out-degree.
+pub fn expected_average_degree<M: EdgeModel>(params: &GenerationParameters<VecSeeds, M>) -> f64 {
+    let (pairs, factor) = sample_pairs(params, CALIBRATION_SAMPLES);
+    estimate_average_degree(params, &pairs, factor)
+}
+
+/// Solves for the [scaling constant](GenerationParameters::c) such that the graph has the requested expected average degree.
+///
+/// The expected average degree is estimated from a fixed sample of node pairs (see [expected_average_degree]) and the constant is found by bisection.
+/// Fails if the requested degree is out of reach for the model, for example because it exceeds the number of nodes.
+/// Models that ignore the scaling constant can not be calibrated.
+pub fn calibrate_scaling<M: EdgeModel>(
+    params: &mut GenerationParameters<VecSeeds, M>,
+    avg_degree: f64,
+) -> anyhow::Result<()> {
+    info!("Calibrating c for an average degree of {}...", avg_degree);
+    if avg_degree.is_nan() || avg_degree <= 0.0 {
+        return Err(anyhow!(
+            "Average degree must be positive, got {}.",
+            avg_degree
+        ));
+    }
+
+    let (pairs, factor) = sample_pairs(params, CALIBRATION_SAMPLES);
+    let mut degree_for = |c: f32| {
+        params.c = c;
+        estimate_average_degree(params, &pairs, factor)
+    };
+
+    // Find a bracket [lo, hi] around the requested degree.
+    let (mut lo, mut hi) = (1.0f32, 1.0f32);
+    while degree_for(hi) < avg_degree {
+        lo = hi;
+        hi *= 2.0;
+        if !hi.is_finite() {
+            return Err(anyhow!(
+                "Average degree {} can not be reached with this model.",
+                avg_degree
+            ));
+        }
+    }
+    while lo == hi || degree_for(lo) > avg_degree {
+        hi = lo;
+        lo /= 2.0;
+        if lo == 0.0 {
+            return Err(anyhow!(
+                "Average degree {} can not be reached with this model.",
+                avg_degree
+            ));
+        }
+    }
+
+    for _ in 0..64 {
+        let mid = lo + (hi - lo) / 2.0;
+        if mid <= lo || mid >= hi {
+            break;
+        }
+        if degree_for(mid) < avg_degree {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+
+    params.c = hi;
+    info!(
+        "Calibrated c = {} (expected average degree {})",
+        params.c,
+        estimate_average_degree(params, &pairs, factor)
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::GenerationParametersBuilder;
+    use rstest::*;
+
+    fn params(v: u64, alpha: f32, graph_kind: GraphKind) -> GenerationParameters<VecSeeds> {
+        GenerationParametersBuilder::new()
+            .alpha(alpha)
+            .vertices(v)
+            .seeds(&[1, 2, 3, 4])
+            .graph_kind(graph_kind)
+            .build()
+            .unwrap()
+    }
+
+    #[rstest]
+    #[case(300, 1.5, GraphKind::Directed, 10.0)]
+    #[case(300, 1.5, GraphKind::Undirected, 10.0)]
+    #[case(300, f32::INFINITY, GraphKind::Undirected, 10.0)]
+    #[case(5000, 2.5, GraphKind::Directed, 20.0)]
+    #[case(5000, 2.5, GraphKind::Undirected, 4.0)]
+    fn calibration_reaches_degree(
+        #[case] v: u64,
+        #[case] alpha: f32,
+        #[case] graph_kind: GraphKind,
+        #[case] avg_degree: f64,
+    ) {
+        let mut params = params(v, alpha, graph_kind);
+        calibrate_scaling(&mut params, avg_degree).unwrap();
+
+        let actual = expected_average_degree(&params);
+        assert!(
+            (actual - avg_degree).abs() < 0.02 * avg_degree,
+            "expected average degree {} != {}",
+            actual,
+            avg_degree
+        );
+    }
+
+    #[test]
+    fn calibration_rejects_unreachable_degree() {
+        let mut params = params(100, 1.5, GraphKind::Undirected);
+        assert!(calibrate_scaling(&mut params, 100.0).is_err());
+        assert!(calibrate_scaling(&mut params, 0.0).is_err());
+    }
+
+    #[rstest]
+    #[case(GraphKind::Directed)]
+    #[case(GraphKind::Undirected)]
+    fn huge_graphs_are_sampled(#[case] graph_kind: GraphKind) {
+        // Only the number of vertices is changed, W of such a graph would take too long to compute.
+        let mut params = params(100, 1.5, graph_kind);
+        params.v = 1 << 40;
+        let (pairs, factor) = sample_pairs(&params, 50);
+        assert_eq!(pairs.len(), 50);
+        assert!(factor >= (params.v - 1) as f64);
+    }
+}
@@ -0,0 +1,341 @@
+//! Pull-based access to the edges of a graph.
+//!
+//! [generate_edges] starts the worker threads of a [GraphGenerator] together with the thread emitting the tiles,
+//! and hands out the edges through an [EdgeIterator], so in-process consumers do not have to wire the channels themselves.
+
+use crate::generator::GraphGenerator;
+use crate::params::ext::GenerationParametersExt;
+use crate::params::VecSeeds;
+use crate::threads::worker_thread;
+use crate::tiles::Tile;
+use crossbeam_channel::Receiver;
+use generator_core::params::{EdgeModel, GenerationParameters};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use tracing::{debug, info};
+
+/// Iterator over the edges of a graph, generated by worker threads in the background.
+///
+/// Worker errors are yielded once all workers stopped, after which the iterator ends.
+/// Dropping the iterator early stops the emission of tiles, waits for the workers to finish the tiles they already received and joins all threads.
+pub struct EdgeIterator {
+    /// The batches of edges sent by the workers.
+    edges: Receiver<Vec<(u64, u64)>>,
+    /// The tiles finished by the workers, [never](crossbeam_channel::never) once the workers are done.
+    finished: Receiver<Tile>,
+    /// The remainder of the current batch.
+    batch: std::vec::IntoIter<(u64, u64)>,
+    /// The worker threads.
+    workers: Vec<JoinHandle<anyhow::Result<()>>>,
+    /// The thread emitting the tiles.
+    tiles: Option<JoinHandle<()>>,
+    /// Tells the tile thread to stop emitting tiles.
+    stop: Arc<AtomicBool>,
+    /// Number of tiles finished so far.
+    finished_tiles: u64,
+    /// Called for every finished tile, see [on_finished_tile](EdgeIterator::on_finished_tile).
+    on_finished: Option<Box<dyn FnMut(Tile) + Send>>,
+    /// Whether all threads have been joined.
+    done: bool,
+}
+
+/// Generates the edges of the graph with `workers` threads of the generator `T`, which is constructed from `backend`.
+///
+/// The edges arrive in the order the workers produce them, which differs between runs with more than one worker.
+///
+/// ```
+/// # use generator_common::edges::generate_edges;
+/// # use generator_common::generator::{EdgeSender, GraphGenerator};
+/// # use generator_common::params::{GenerationParameters, GenerationParametersBuilder, VecSeeds};
+/// # use generator_common::tiles::Tile;
+/// # use crossbeam_channel::{Receiver, Sender};
+/// /// Connects the first node of every tile to itself.
+/// struct Diagonal;
+///
+/// impl GraphGenerator for Diagonal {
+///     type ConstructArgument = ();
+///
+///     fn new(_: ()) -> anyhow::Result<Self> {
+///         Ok(Diagonal)
+///     }
+///
+///     fn generate(
+///         &self,
+///         edges: EdgeSender,
+///         finished: Sender<Tile>,
+///         tiles: Receiver<Tile>,
+///         _: &GenerationParameters<VecSeeds>,
+///     ) -> anyhow::Result<()> {
+///         for tile in tiles {
+///             edges.send(vec![(tile.0 .0, tile.0 .0)])?;
+///             finished.send(tile)?;
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let params = GenerationParametersBuilder::new().vertices(100).tile_size(10).build()?;
+/// let edges = generate_edges::<Diagonal, _>(&params, (), 4).collect::<anyhow::Result<Vec<_>>>()?;
+/// assert_eq!(edges.len(), 100);
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn generate_edges<T: GraphGenerator<M>, M: EdgeModel + Send + 'static>(
+    params: &GenerationParameters<VecSeeds, M>,
+    backend: T::ConstructArgument,
+    workers: usize,
+) -> EdgeIterator {
+    let (tile_sender, tile_receiver) = crossbeam_channel::bounded(5);
+    let (edge_sender, edge_receiver) = crossbeam_channel::bounded(100);
+    let (finish_sender, finish_receiver) = crossbeam_channel::bounded(10000);
+
+    let workers = (0u64..(workers as u64))
+        .map(|i| {
+            let sender = edge_sender.clone();
+            let receiver = tile_receiver.clone();
+            let finisher = finish_sender.clone();
+            let params = params.clone();
+            let backend = backend.clone();
+            std::thread::spawn(move || {
+                worker_thread::<T, M>(i, backend, sender, finisher, receiver, &params)
+            })
+        })
+        .collect();
+    // Only the workers hold these, such that the channels close once the workers are done.
+    drop(edge_sender);
+    drop(tile_receiver);
+    drop(finish_sender);
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let tiles = {
+        let params = params.clone();
+        let stop = stop.clone();
+        std::thread::spawn(move || {
+            info!("Emitting tiles...");
+            for tile in params.tiles() {
+                // Sending fails if all workers are gone, which the iterator reports.
+                if stop.load(Ordering::Relaxed) || tile_sender.send(tile).is_err() {
+                    info!("Stopped emitting tiles.");
+                    return;
+                }
+            }
+            info!("Tiles are generated!");
+        })
+    };
+
+    EdgeIterator {
+        edges: edge_receiver,
+        finished: finish_receiver,
+        batch: Vec::new().into_iter(),
+        workers,
+        tiles: Some(tiles),
+        stop,
+        finished_tiles: 0,
+        on_finished: None,
+        done: false,
+    }
+}
+
+impl EdgeIterator {
+    /// Number of tiles the workers have finished so far.
+    pub fn finished_tiles(&self) -> u64 {
+        self.finished_tiles
+    }
+
+    /// Calls `f` for every tile the workers finish, for example to report progress.
+    ///
+    /// The tiles are reported while the edges are consumed, from the thread consuming them.
+    pub fn on_finished_tile<F: FnMut(Tile) + Send + 'static>(mut self, f: F) -> Self {
+        self.on_finished = Some(Box::new(f));
+        self
+    }
+
+    /// The rest of the current batch of edges, or else the next batch sent by a worker.
+    ///
+    /// This hands out the edges in the batches the workers produce, it can be mixed with [next](Iterator::next) without losing edges.
+    pub fn next_batch(&mut self) -> Option<anyhow::Result<Vec<(u64, u64)>>> {
+        if !self.batch.as_slice().is_empty() {
+            let batch = std::mem::replace(&mut self.batch, Vec::new().into_iter());
+            return Some(Ok(batch.collect()));
+        }
+        self.receive_batch()
+    }
+
+    /// Joins all threads, returning the first error of a worker.
+    fn join(&mut self) -> anyhow::Result<()> {
+        self.done = true;
+        let mut result = Ok(());
+        for (i, h) in self.workers.drain(..).enumerate() {
+            let r = h
+                .join()
+                .unwrap_or_else(|_| Err(anyhow::anyhow!("Worker {} panicked.", i)));
+            if result.is_ok() {
+                result = r;
+            }
+        }
+        if let Some(h) = self.tiles.take() {
+            h.join()
+                .map_err(|_| anyhow::anyhow!("Tile thread panicked."))?;
+        }
+        result
+    }
+
+    /// Receives the next batch of edges, counting the finished tiles while waiting.
+    ///
+    /// Once the workers are done, the threads are joined and the first worker error is returned.
+    fn receive_batch(&mut self) -> Option<anyhow::Result<Vec<(u64, u64)>>> {
+        if self.done {
+            return None;
+        }
+        loop {
+            crossbeam_channel::select! {
+                recv(self.edges) -> batch => match batch {
+                    Ok(batch) => return Some(Ok(batch)),
+                    Err(_) => break,
+                },
+                recv(self.finished) -> tile => match tile {
+                    Ok(tile) => {
+                        debug!("Finished block {:?}.", tile);
+                        self.finished_tiles += 1;
+                        if let Some(f) = self.on_finished.as_mut() {
+                            f(tile);
+                        }
+                    }
+                    Err(_) => self.finished = crossbeam_channel::never(),
+                },
+            }
+        }
+        // The edge channel is closed, count the tiles finished last.
+        for tile in self.finished.try_iter() {
+            self.finished_tiles += 1;
+            if let Some(f) = self.on_finished.as_mut() {
+                f(tile);
+            }
+        }
+        self.join().err().map(Err)
+    }
+}
+
+impl Iterator for EdgeIterator {
+    type Item = anyhow::Result<(u64, u64)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(edge) = self.batch.next() {
+                return Some(Ok(edge));
+            }
+            match self.receive_batch()? {
+                Ok(batch) => self.batch = batch.into_iter(),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+impl Drop for EdgeIterator {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // The workers block on sending edges, so the remaining ones are received and discarded.
+        while self.receive_batch().is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::EdgeSender;
+    use crate::params::GenerationParametersBuilder;
+    use crossbeam_channel::Sender;
+
+    /// Emits the corner of every tile as an edge, and fails on the tile with the given corner.
+    struct TileCorners(Option<(u64, u64)>);
+
+    impl GraphGenerator for TileCorners {
+        type ConstructArgument = Option<(u64, u64)>;
+
+        fn new(fail_at: Self::ConstructArgument) -> anyhow::Result<Self> {
+            Ok(TileCorners(fail_at))
+        }
+
+        fn generate(
+            &self,
+            edges: EdgeSender,
+            finished: Sender<Tile>,
+            tiles: Receiver<Tile>,
+            _: &GenerationParameters<VecSeeds>,
+        ) -> anyhow::Result<()> {
+            for (start, end) in tiles {
+                if Some(start) == self.0 {
+                    anyhow::bail!("Failed at {:?}.", start);
+                }
+                edges.send(vec![start])?;
+                finished.send((start, end))?;
+            }
+            Ok(())
+        }
+    }
+
+    fn params() -> GenerationParameters<VecSeeds> {
+        GenerationParametersBuilder::new()
+            .vertices(100)
+            .tile_size(10)
+            .seed(20)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn yields_every_edge() {
+        let mut iter = generate_edges::<TileCorners, _>(&params(), None, 3);
+        let mut edges = iter.by_ref().collect::<anyhow::Result<Vec<_>>>().unwrap();
+        edges.sort_unstable();
+        let mut expected = params().tiles().map(|t| t.0).collect::<Vec<_>>();
+        expected.sort_unstable();
+        assert_eq!(edges, expected);
+        assert_eq!(iter.finished_tiles(), 100);
+    }
+
+    #[test]
+    fn reports_finished_tiles_and_batches() {
+        let reported = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut iter = generate_edges::<TileCorners, _>(&params(), None, 2).on_finished_tile({
+            let reported = reported.clone();
+            move |tile| reported.lock().unwrap().push(tile)
+        });
+        let mut edges = vec![iter.next().unwrap().unwrap()];
+        while let Some(batch) = iter.next_batch() {
+            edges.extend(batch.unwrap());
+        }
+        assert_eq!(edges.len(), 100);
+
+        let mut reported = reported.lock().unwrap().clone();
+        reported.sort_unstable();
+        let mut expected = params().tiles().collect::<Vec<_>>();
+        expected.sort_unstable();
+        assert_eq!(reported, expected);
+    }
+
+    #[test]
+    fn forwards_worker_errors() {
+        let results =
+            generate_edges::<TileCorners, _>(&params(), Some((50, 50)), 1).collect::<Vec<_>>();
+        let (last, edges) = results.split_last().unwrap();
+        assert!(edges.iter().all(|e| e.is_ok()));
+        assert!(last.as_ref().unwrap_err().to_string().contains("(50, 50)"));
+    }
+
+    #[test]
+    fn dropping_early_stops_the_threads() {
+        let mut iter = generate_edges::<TileCorners, _>(&params(), None, 2);
+        assert!(iter.next().unwrap().is_ok());
+        drop(iter);
+    }
+
+    #[test]
+    fn no_workers_yield_no_edges() {
+        assert_eq!(
+            generate_edges::<TileCorners, _>(&params(), None, 0).count(),
+            0
+        );
+    }
+}
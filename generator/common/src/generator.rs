@@ -1,11 +1,14 @@
 use crate::params::VecSeeds;
 use crate::tiles::Tile;
 use crossbeam_channel::{Receiver, Sender};
-use generator_core::params::GenerationParameters;
+use generator_core::params::{EdgeModel, GenerationParameters, Model};
 
 pub type EdgeSender = Sender<Vec<(u64, u64)>>;
 
-pub trait GraphGenerator: Sized {
+/// A backend that generates the edges of the tiles it receives.
+///
+/// Generators are parameterized by the [EdgeModel] they support, which defaults to the built-in [Model]s.
+pub trait GraphGenerator<M: EdgeModel = Model>: Sized {
     type ConstructArgument: Clone + Send + 'static;
 
     fn new(arg: Self::ConstructArgument) -> anyhow::Result<Self>;
@@ -15,6 +18,6 @@ pub trait GraphGenerator: Sized {
         output_sender: EdgeSender,
         finished_job_sender: Sender<Tile>,
         new_job_receiver: Receiver<Tile>,
-        params: &GenerationParameters<VecSeeds>,
+        params: &GenerationParameters<VecSeeds, M>,
     ) -> anyhow::Result<()>;
 }
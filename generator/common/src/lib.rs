@@ -2,6 +2,7 @@
 #![warn(clippy::missing_docs_in_private_items)]
 
 pub mod algorithm;
+pub mod edges;
 pub mod generator;
 pub mod params;
 pub mod random;
@@ -9,4 +10,6 @@ pub mod threads;
 pub mod tiles;
 pub mod memory;
 
+pub use edges::{generate_edges, EdgeIterator};
+pub use generator_core::model;
 pub use generator_core::MAX_DIMS;
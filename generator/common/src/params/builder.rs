@@ -0,0 +1,406 @@
+//! Builder for [GenerationParameters] with named setters.
+
+use crate::algorithm::{calibrate_scaling, generate_parameters};
+use crate::params::ext::GenerationParametersExt;
+use crate::params::{NodeAttributes, VecSeeds};
+use crate::random::{self, derive_seeds, generate_master_seed, ParetoDistribution};
+use generator_core::params::{
+    EdgeModel, EdgeRandomness, GenerationParameters, Geometry, GraphKind, HashFunction, Model,
+    Norm, RandomMode,
+};
+
+/// Where the seeds of the graph come from.
+#[derive(Clone, Debug)]
+enum SeedSource {
+    /// A random master seed.
+    Random,
+    /// The seeds are derived from this master seed, see [derive_seeds].
+    Master(u64),
+    /// The seeds are given directly.
+    Explicit(Vec<u64>),
+}
+
+/// Builds [GenerationParameters] from named settings.
+///
+/// Every setting has a default, so only the ones that differ have to be given:
+/// a directed GIRG with 1000 nodes in 2 dimensions on the torus, `alpha = 1.5` and pareto weights with `x_min = 1` and exponent `1.5`.
+/// The seeds are derived from a random master seed unless [seed](Self::seed) or [seeds](Self::seeds) is used.
+/// [build](Self::build) checks the settings and returns an error instead of panicking, see [ValidationError](crate::params::ValidationError).
+///
+/// ```
+/// # use generator_common::params::GenerationParametersBuilder;
+/// let params = GenerationParametersBuilder::new()
+///     .vertices(10_000)
+///     .dimensions(1)
+///     .alpha(2.0)
+///     .seed(42)
+///     .build()
+///     .unwrap();
+/// assert_eq!(params.seeds.seeds.len(), 3);
+/// ```
+#[derive(Clone, Debug)]
+pub struct GenerationParametersBuilder<M: EdgeModel = Model> {
+    dims: usize,
+    weights: random::WeightDistribution,
+    alpha: f32,
+    c: f32,
+    avg_degree: Option<f64>,
+    v: u64,
+    seeds: SeedSource,
+    tile_size: u64,
+    edgebuffer_size: u64,
+    random_mode: RandomMode,
+    gpu_blocks: u32,
+    skip_sampling: bool,
+    shard_index: usize,
+    shard_count: usize,
+    graph_kind: GraphKind,
+    edge_randomness: EdgeRandomness,
+    hash: HashFunction,
+    norm: Norm,
+    geometry: Geometry,
+    model: M,
+    nodes: Option<NodeAttributes>,
+}
+
+impl Default for GenerationParametersBuilder {
+    fn default() -> Self {
+        Self {
+            dims: 2,
+            weights: ParetoDistribution::new(1.0, 1.5).into(),
+            alpha: 1.5,
+            c: 1.0,
+            avg_degree: None,
+            v: 1000,
+            seeds: SeedSource::Random,
+            tile_size: 1000,
+            edgebuffer_size: 1024,
+            random_mode: RandomMode::PreGenerate,
+            gpu_blocks: 0,
+            skip_sampling: false,
+            shard_index: 0,
+            shard_count: 1,
+            graph_kind: GraphKind::Directed,
+            edge_randomness: EdgeRandomness::Bits32,
+            hash: HashFunction::Murmur3,
+            norm: Norm::Max,
+            geometry: Geometry::Torus,
+            model: Model::Girg,
+            nodes: None,
+        }
+    }
+}
+
+impl GenerationParametersBuilder {
+    /// A builder with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<M: EdgeModel> GenerationParametersBuilder<M> {
+    /// Number of spatial dimensions.
+    pub fn dimensions(mut self, dims: usize) -> Self {
+        self.dims = dims;
+        self
+    }
+
+    /// Distribution of the weights.
+    pub fn weights<D: Into<random::WeightDistribution>>(mut self, weights: D) -> Self {
+        self.weights = weights.into();
+        self
+    }
+
+    /// Alpha value of the probability function, infinity yields the threshold model.
+    pub fn alpha(mut self, alpha: f32) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    /// Scaling constant c of the probability function.
+    pub fn scaling(mut self, c: f32) -> Self {
+        self.c = c;
+        self
+    }
+
+    /// Calibrates the scaling constant to reach this expected average degree, see [calibrate_scaling].
+    pub fn avg_degree(mut self, avg_degree: f64) -> Self {
+        self.avg_degree = Some(avg_degree);
+        self
+    }
+
+    /// Number of vertices.
+    pub fn vertices(mut self, v: u64) -> Self {
+        self.v = v;
+        self
+    }
+
+    /// Derives the seeds from this master seed, see [derive_seeds].
+    pub fn seed(mut self, master: u64) -> Self {
+        self.seeds = SeedSource::Master(master);
+        self
+    }
+
+    /// Uses these seeds: one for the weights, one for the edges and one per dimension.
+    pub fn seeds(mut self, seeds: &[u64]) -> Self {
+        self.seeds = SeedSource::Explicit(seeds.to_vec());
+        self
+    }
+
+    /// Size of the square tiles the pairs are split into.
+    pub fn tile_size(mut self, tile_size: u64) -> Self {
+        self.tile_size = tile_size;
+        self
+    }
+
+    /// Number of edges buffered before they are sent on.
+    pub fn edgebuffer_size(mut self, edgebuffer_size: u64) -> Self {
+        self.edgebuffer_size = edgebuffer_size;
+        self
+    }
+
+    /// How the weights and positions of the nodes are obtained, see [RandomMode].
+    pub fn random_mode(mut self, random_mode: RandomMode) -> Self {
+        self.random_mode = random_mode;
+        self
+    }
+
+    /// Number of blocks used by the GPU generator, 0 lets the generator decide.
+    pub fn gpu_blocks(mut self, gpu_blocks: u32) -> Self {
+        self.gpu_blocks = gpu_blocks;
+        self
+    }
+
+    /// Use geometric skip sampling in the CPU generator, see [GenerationParameters::skip_sampling].
+    pub fn skip_sampling(mut self, skip_sampling: bool) -> Self {
+        self.skip_sampling = skip_sampling;
+        self
+    }
+
+    /// Only generate the tiles of shard `index` out of `count`.
+    pub fn shard(mut self, index: usize, count: usize) -> Self {
+        self.shard_index = index;
+        self.shard_count = count;
+        self
+    }
+
+    /// Kind of graph to generate.
+    pub fn graph_kind(mut self, graph_kind: GraphKind) -> Self {
+        self.graph_kind = graph_kind;
+        self
+    }
+
+    /// Randomness that decides whether a pair is an edge.
+    pub fn edge_randomness(mut self, edge_randomness: EdgeRandomness) -> Self {
+        self.edge_randomness = edge_randomness;
+        self
+    }
+
+    /// Hash family all random numbers are derived from.
+    pub fn hash(mut self, hash: HashFunction) -> Self {
+        self.hash = hash;
+        self
+    }
+
+    /// Norm used to compute distances.
+    pub fn norm(mut self, norm: Norm) -> Self {
+        self.norm = norm;
+        self
+    }
+
+    /// Space the positions live in.
+    pub fn geometry(mut self, geometry: Geometry) -> Self {
+        self.geometry = geometry;
+        self
+    }
+
+    /// Uses the given weights and/or positions instead of generating them.
+    ///
+    /// These determine the number of vertices, and the number of dimensions if they hold positions.
+    pub fn nodes(mut self, nodes: NodeAttributes) -> Self {
+        self.nodes = Some(nodes);
+        self
+    }
+
+    /// The [EdgeModel] of the graph.
+    pub fn model<N: EdgeModel>(self, model: N) -> GenerationParametersBuilder<N> {
+        GenerationParametersBuilder {
+            dims: self.dims,
+            weights: self.weights,
+            alpha: self.alpha,
+            c: self.c,
+            avg_degree: self.avg_degree,
+            v: self.v,
+            seeds: self.seeds,
+            tile_size: self.tile_size,
+            edgebuffer_size: self.edgebuffer_size,
+            random_mode: self.random_mode,
+            gpu_blocks: self.gpu_blocks,
+            skip_sampling: self.skip_sampling,
+            shard_index: self.shard_index,
+            shard_count: self.shard_count,
+            graph_kind: self.graph_kind,
+            edge_randomness: self.edge_randomness,
+            hash: self.hash,
+            norm: self.norm,
+            geometry: self.geometry,
+            model,
+            nodes: self.nodes,
+        }
+    }
+
+    /// Checks the settings (see [validate](crate::params::validate::validate)) and computes the parameters, including W and the calibrated scaling constant.
+    pub fn build(self) -> anyhow::Result<GenerationParameters<VecSeeds, M>> {
+        // Loaded attributes determine the number of vertices and dimensions.
+        let dims = self
+            .nodes
+            .as_ref()
+            .and_then(|n| n.num_dimensions())
+            .unwrap_or(self.dims);
+
+        let (master, seeds) = match self.seeds {
+            SeedSource::Random => {
+                let master = generate_master_seed();
+                (Some(master), derive_seeds(master, dims + 2))
+            }
+            SeedSource::Master(master) => (Some(master), derive_seeds(master, dims + 2)),
+            SeedSource::Explicit(seeds) => (None, seeds),
+        };
+
+        let mut params = GenerationParameters {
+            seeds: VecSeeds {
+                seeds,
+                master,
+                nodes: None,
+            },
+            random_mode: self.random_mode,
+            gpu_blocks: self.gpu_blocks,
+            skip_sampling: self.skip_sampling,
+            dims,
+            weights: self.weights,
+            alpha: self.alpha,
+            c: self.c,
+            w: 0.0,
+            v: self.v,
+            tile_size: self.tile_size,
+            edgebuffer_size: self.edgebuffer_size,
+            shard_index: self.shard_index,
+            shard_count: self.shard_count,
+            graph_kind: self.graph_kind,
+            edge_randomness: self.edge_randomness,
+            hash: self.hash,
+            norm: self.norm,
+            geometry: self.geometry,
+            model: self.model,
+        };
+
+        params.validate()?;
+        match self.nodes {
+            Some(nodes) => params = params.with_nodes(nodes)?,
+            None => generate_parameters(&mut params),
+        }
+        if let Some(avg_degree) = self.avg_degree {
+            calibrate_scaling(&mut params, avg_degree)?;
+        }
+
+        Ok(params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::ext::GenerationParametersConstructExt;
+    use rstest::rstest;
+
+    #[test]
+    #[allow(deprecated)]
+    fn matches_positional_constructor() {
+        let built = GenerationParametersBuilder::new()
+            .dimensions(3)
+            .weights(ParetoDistribution::new(2.0, 1.8))
+            .alpha(2.5)
+            .vertices(500)
+            .seeds(&[1, 2, 3, 4, 5])
+            .tile_size(64)
+            .edgebuffer_size(256)
+            .random_mode(RandomMode::OnDemand)
+            .gpu_blocks(8)
+            .shard(1, 3)
+            .build()
+            .unwrap();
+        let positional = GenerationParameters::from_seeds(
+            3,
+            ParetoDistribution::new(2.0, 1.8),
+            2.5,
+            500,
+            &[1, 2, 3, 4, 5],
+            64,
+            256,
+            false,
+            8,
+            1,
+            3,
+        );
+
+        assert_eq!(built.seeds.seeds, positional.seeds.seeds);
+        assert_eq!(built.w, positional.w);
+        assert_eq!(
+            (built.tile_size, built.edgebuffer_size, built.gpu_blocks),
+            (64, 256, 8)
+        );
+        assert_eq!((built.shard_index, built.shard_count), (1, 3));
+        assert_eq!(built.random_mode, RandomMode::OnDemand);
+    }
+
+    #[test]
+    fn master_seed_is_recorded() {
+        let params = GenerationParametersBuilder::new().seed(7).build().unwrap();
+        assert_eq!(params.seeds.master, Some(7));
+        assert_eq!(params.seeds.seeds, derive_seeds(7, 4));
+    }
+
+    #[rstest]
+    #[case(GenerationParametersBuilder::new().dimensions(0))]
+    #[case(GenerationParametersBuilder::new().seeds(&[1, 2, 3]))]
+    #[case(GenerationParametersBuilder::new().shard(2, 2))]
+    #[case(GenerationParametersBuilder::new().shard(0, 0))]
+    #[case(GenerationParametersBuilder::new().tile_size(0))]
+    fn invalid_settings_are_errors(#[case] builder: GenerationParametersBuilder) {
+        assert!(builder.build().is_err());
+    }
+
+    #[rstest]
+    #[case(Some(vec![1.0, 0.0]), None)]
+    #[case(Some(vec![1.0, -2.0]), None)]
+    #[case(Some(vec![f32::NAN, 1.0]), None)]
+    #[case(Some(vec![f32::INFINITY, 1.0]), None)]
+    #[case(None, Some(vec![vec![0.5, 1.5], vec![0.1, 0.2]]))]
+    #[case(None, Some(vec![vec![0.5, 0.5], vec![-0.1, 0.2]]))]
+    #[case(None, Some(vec![vec![0.5, 1.0], vec![0.1, 0.2]]))]
+    #[case(None, Some(vec![vec![0.5, f32::NAN], vec![0.1, 0.2]]))]
+    fn invalid_nodes_are_errors(
+        #[case] weights: Option<Vec<f32>>,
+        #[case] positions: Option<Vec<Vec<f32>>>,
+    ) {
+        let nodes = NodeAttributes::new(weights, positions).unwrap();
+        assert!(GenerationParametersBuilder::new()
+            .nodes(nodes)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn valid_nodes_are_accepted() {
+        let nodes = NodeAttributes::new(
+            Some(vec![1.0, 3.5]),
+            Some(vec![vec![0.0, 0.25], vec![0.999, 0.5]]),
+        )
+        .unwrap();
+        let params = GenerationParametersBuilder::new()
+            .nodes(nodes)
+            .build()
+            .unwrap();
+        assert_eq!(params.v, 2);
+    }
+}
@@ -1,19 +1,42 @@
 use crate::algorithm::generate_parameters;
-use crate::params::VecSeeds;
+use crate::params::{GenerationParametersBuilder, NodeAttributes, ValidationError, VecSeeds};
 use crate::random;
-use generator_core::params::{GenerationParameters, SeedEnum};
+use generator_core::params::{EdgeModel, GenerationParameters, GraphKind, RandomMode};
+use std::sync::Arc;
 
-pub trait GenerationParametersExt {
+pub trait GenerationParametersExt: Sized {
+    /// Weight of node j, taken from the [NodeAttributes] if they hold weights.
+    fn node_weight(&self, j: u64) -> f32;
+    /// Writes the position of node j into `p`, taken from the [NodeAttributes] if they hold positions.
+    fn fill_node_position(&self, j: u64, p: &mut [f32]);
     fn compute_weights(&self) -> Vec<f32>;
     fn compute_position(&self, j: u64) -> Vec<f32>;
     fn compute_positions(&self) -> Vec<Vec<f32>>;
     fn compute_interleaved_variables(&self) -> Vec<f32>;
     fn tiles(&self) -> Box<dyn Iterator<Item = crate::tiles::Tile>>;
     fn num_tiles(&self) -> u64;
+    /// Replaces the [EdgeModel] of the graph, recomputing the derived parameters.
+    fn with_model<N: EdgeModel>(self, model: N) -> GenerationParameters<VecSeeds, N>;
+    /// Uses the given weights and/or positions instead of generating them.
+    ///
+    /// The number of nodes is taken from the attributes and W is recomputed.
+    /// Fails if the positions do not match the number of dimensions of the graph, if a coordinate
+    /// lies outside of the unit cube [0, 1) or if a weight is not finite and positive.
+    fn with_nodes(self, nodes: NodeAttributes) -> anyhow::Result<Self>;
+    /// Checks all constraints on the parameters, see [validate](crate::params::validate::validate).
+    fn validate(&self) -> Result<(), ValidationError>;
+}
+
+/// Constructors for the parameters of a graph using the built-in [Model](crate::params::Model)s.
+///
+/// Use [GenerationParametersExt::with_model] to switch to any other [EdgeModel].
+/// These panic on invalid arguments, [GenerationParametersBuilder] has named settings and returns errors instead.
+pub trait GenerationParametersConstructExt {
+    #[deprecated(note = "panics on invalid arguments, use GenerationParametersBuilder instead")]
     #[allow(clippy::too_many_arguments)]
-    fn new(
+    fn new<D: Into<random::WeightDistribution>>(
         num_dimensions: usize,
-        pareto: random::ParetoDistribution,
+        weights: D,
         alpha: f32,
         v: u64,
         tile_size: u64,
@@ -24,10 +47,11 @@ pub trait GenerationParametersExt {
         shard_count: usize,
     ) -> Self;
 
+    #[deprecated(note = "panics on invalid arguments, use GenerationParametersBuilder instead")]
     #[allow(clippy::too_many_arguments)]
-    fn from_seeds(
+    fn from_seeds<D: Into<random::WeightDistribution>>(
         num_dimensions: usize,
-        pareto: random::ParetoDistribution,
+        weights: D,
         alpha: f32,
         v: u64,
         seeds: &[u64],
@@ -40,15 +64,29 @@ pub trait GenerationParametersExt {
     ) -> Self;
 }
 
-impl GenerationParametersExt for GenerationParameters<VecSeeds> {
+impl<M: EdgeModel> GenerationParametersExt for GenerationParameters<VecSeeds, M> {
+    fn node_weight(&self, j: u64) -> f32 {
+        match self.seeds.nodes.as_ref().and_then(|n| n.weights()) {
+            Some(w) => w[j as usize],
+            None => self.compute_weight(j),
+        }
+    }
+
+    fn fill_node_position(&self, j: u64, p: &mut [f32]) {
+        match self.seeds.nodes.as_ref().and_then(|n| n.positions()) {
+            Some(ps) => p.copy_from_slice(&ps[j as usize]),
+            None => self.fill_dims(j, p),
+        }
+    }
+
     fn compute_weights(&self) -> Vec<f32> {
-        (0..self.v).map(|j| self.compute_weight(j)).collect()
+        (0..self.v).map(|j| self.node_weight(j)).collect()
     }
 
     fn compute_position(&self, j: u64) -> Vec<f32> {
-        (0..self.num_dimensions())
-            .map(|d| self.compute_property(j, SeedEnum::Dimension(d)))
-            .collect()
+        let mut p = vec![0.0f32; self.num_dimensions()];
+        self.fill_node_position(j, &mut p);
+        p
     }
 
     fn compute_positions(&self) -> Vec<Vec<f32>> {
@@ -58,33 +96,115 @@ impl GenerationParametersExt for GenerationParameters<VecSeeds> {
     fn compute_interleaved_variables(&self) -> Vec<f32> {
         (0..self.v)
             .flat_map(|j| {
-                vec![self.compute_weight(j)]
+                vec![self.node_weight(j)]
                     .into_iter()
-                    .chain(
-                        (0..self.num_dimensions())
-                            .map(|d| self.compute_property(j, SeedEnum::Dimension(d))),
-                    )
+                    .chain(self.compute_position(j))
                     .collect::<Vec<f32>>()
             })
             .collect()
     }
 
     fn tiles(&self) -> Box<dyn Iterator<Item = crate::tiles::Tile>> {
-        Box::new(
-            crate::tiles::TilesIterator::new(self.v, self.tile_size)
-                .skip(self.shard_index)
-                .step_by(self.shard_count),
-        )
+        let tiles = match self.graph_kind {
+            GraphKind::Directed => crate::tiles::TilesIterator::new(self.v, self.tile_size),
+            GraphKind::Undirected => {
+                crate::tiles::TilesIterator::new_upper_triangle(self.v, self.tile_size)
+            }
+        };
+        Box::new(tiles.skip(self.shard_index).step_by(self.shard_count))
     }
 
     fn num_tiles(&self) -> u64 {
-        num_integer::div_ceil(self.v, self.tile_size).pow(2) / (self.shard_count as u64)
+        crate::tiles::TilesIterator::count_tiles(
+            self.v,
+            self.tile_size,
+            self.graph_kind == GraphKind::Undirected,
+        ) / (self.shard_count as u64)
     }
 
+    fn with_model<N: EdgeModel>(self, model: N) -> GenerationParameters<VecSeeds, N> {
+        let mut s = GenerationParameters {
+            seeds: self.seeds,
+            random_mode: self.random_mode,
+            gpu_blocks: self.gpu_blocks,
+            skip_sampling: self.skip_sampling,
+            dims: self.dims,
+            weights: self.weights,
+            alpha: self.alpha,
+            c: self.c,
+            w: self.w,
+            v: self.v,
+            tile_size: self.tile_size,
+            edgebuffer_size: self.edgebuffer_size,
+            shard_index: self.shard_index,
+            shard_count: self.shard_count,
+            graph_kind: self.graph_kind,
+            edge_randomness: self.edge_randomness,
+            hash: self.hash,
+            norm: self.norm,
+            geometry: self.geometry,
+            model,
+        };
+
+        // The weights depend on the model.
+        generate_parameters(&mut s);
+
+        s
+    }
+
+    fn with_nodes(mut self, nodes: NodeAttributes) -> anyhow::Result<Self> {
+        if let Some(d) = nodes.num_dimensions() {
+            if d != self.num_dimensions() {
+                anyhow::bail!(
+                    "Positions have {} dimensions, the graph has {}.",
+                    d,
+                    self.num_dimensions()
+                );
+            }
+        }
+        if let Some(w) = nodes.weights() {
+            if let Some(i) = w.iter().position(|&w| !w.is_finite() || w <= 0.0f32) {
+                anyhow::bail!(
+                    "Weight of node {} is {}, weights must be finite and positive.",
+                    i,
+                    w[i]
+                );
+            }
+        }
+        if let Some(p) = nodes.positions() {
+            for (i, x) in p.iter().enumerate() {
+                if let Some(&c) = x.iter().find(|&&c| !(0.0f32..1.0f32).contains(&c)) {
+                    anyhow::bail!(
+                        "Position of node {} has coordinate {} outside of the unit cube [0, 1).",
+                        i,
+                        c
+                    );
+                }
+            }
+        }
+        if let Some(v) = nodes.num_nodes() {
+            self.v = v as u64;
+        }
+
+        // The attributes only live on the host, so the numbers are always pregenerated.
+        self.random_mode = RandomMode::PreGenerate;
+        self.seeds.nodes = Some(Arc::new(nodes));
+        generate_parameters(&mut self);
+
+        Ok(self)
+    }
+
+    fn validate(&self) -> Result<(), ValidationError> {
+        crate::params::validate::validate(self)
+    }
+}
+
+#[allow(deprecated)]
+impl GenerationParametersConstructExt for GenerationParameters<VecSeeds> {
     #[allow(clippy::too_many_arguments)]
-    fn new(
+    fn new<D: Into<random::WeightDistribution>>(
         num_dimensions: usize,
-        pareto: random::ParetoDistribution,
+        weights: D,
         alpha: f32,
         v: u64,
         tile_size: u64,
@@ -98,7 +218,7 @@ impl GenerationParametersExt for GenerationParameters<VecSeeds> {
 
         Self::from_seeds(
             num_dimensions,
-            pareto,
+            weights,
             alpha,
             v,
             &seeds,
@@ -112,9 +232,9 @@ impl GenerationParametersExt for GenerationParameters<VecSeeds> {
     }
 
     #[allow(clippy::too_many_arguments)]
-    fn from_seeds(
+    fn from_seeds<D: Into<random::WeightDistribution>>(
         num_dimensions: usize,
-        pareto: random::ParetoDistribution,
+        weights: D,
         alpha: f32,
         v: u64,
         seeds: &[u64],
@@ -125,34 +245,22 @@ impl GenerationParametersExt for GenerationParameters<VecSeeds> {
         shard_index: usize,
         shard_count: usize,
     ) -> Self {
-        if seeds.len() != num_dimensions + 2 {
-            panic!(
-                "Invalid seeds length: {} != {}",
-                seeds.len(),
-                num_dimensions + 2
-            );
-        }
-
-        let mut s = Self {
-            seeds: VecSeeds {
-                seeds: Vec::from(seeds),
-            },
-            pregenerate_numbers,
-            gpu_blocks,
-            dims: num_dimensions,
-            pareto,
-            alpha,
-            w: 0.0,
-            v,
-            tile_size,
-            edgebuffer_size,
-            shard_index,
-            shard_count,
-        };
-
-        // Initialize
-        generate_parameters(&mut s);
-
-        s
+        GenerationParametersBuilder::new()
+            .dimensions(num_dimensions)
+            .weights(weights)
+            .alpha(alpha)
+            .vertices(v)
+            .seeds(seeds)
+            .tile_size(tile_size)
+            .edgebuffer_size(edgebuffer_size)
+            .random_mode(if pregenerate_numbers {
+                RandomMode::PreGenerate
+            } else {
+                RandomMode::OnDemand
+            })
+            .gpu_blocks(gpu_blocks)
+            .shard(shard_index, shard_count)
+            .build()
+            .unwrap_or_else(|e| panic!("Invalid generation parameters: {}", e))
     }
 }
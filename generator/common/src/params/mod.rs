@@ -1,10 +1,25 @@
 pub use generator_core::params::*;
 
+pub mod builder;
 pub mod ext;
+pub mod validate;
 
-#[derive(Debug, Clone)]
+pub use builder::GenerationParametersBuilder;
+pub use validate::{ParameterError, ValidationError};
+
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct VecSeeds {
     pub seeds: Vec<u64>,
+    /// Master seed the seeds were derived from, if any. See [derive_seeds](crate::random::derive_seeds).
+    pub master: Option<u64>,
+    /// Node attributes given instead of generated from the seeds. See [NodeAttributes].
+    ///
+    /// These are not serialized, they have to be loaded from their own files again.
+    #[serde(skip)]
+    pub nodes: Option<Arc<NodeAttributes>>,
 }
 
 impl SeedGettable for VecSeeds {
@@ -17,3 +32,123 @@ impl SeedGettable for VecSeeds {
         self.seeds[i]
     }
 }
+
+/// Per-node weights and/or positions, for example loaded from a file.
+///
+/// These replace the weights and positions that are otherwise computed from the seeds.
+/// As they only live on the host, graphs using them always pregenerate their numbers.
+/// See [with_nodes](ext::GenerationParametersExt::with_nodes).
+#[derive(Clone, Default)]
+pub struct NodeAttributes {
+    weights: Option<Vec<f32>>,
+    positions: Option<Vec<Vec<f32>>>,
+}
+
+impl NodeAttributes {
+    /// Bundles the given weights and positions, checking that they describe the same number of nodes.
+    ///
+    /// All positions must have the same number of dimensions.
+    pub fn new(
+        weights: Option<Vec<f32>>,
+        positions: Option<Vec<Vec<f32>>>,
+    ) -> anyhow::Result<Self> {
+        if let (Some(w), Some(p)) = (weights.as_ref(), positions.as_ref()) {
+            if w.len() != p.len() {
+                anyhow::bail!(
+                    "Got {} weights but {} positions, expected one of each per node.",
+                    w.len(),
+                    p.len()
+                );
+            }
+        }
+        if let Some(p) = positions.as_ref() {
+            if let Some(i) = p.iter().position(|x| x.len() != p[0].len()) {
+                anyhow::bail!(
+                    "Position of node {} has {} dimensions, expected {}.",
+                    i,
+                    p[i].len(),
+                    p[0].len()
+                );
+            }
+        }
+
+        Ok(Self { weights, positions })
+    }
+
+    /// Number of nodes, [None] if neither weights nor positions are given.
+    pub fn num_nodes(&self) -> Option<usize> {
+        self.weights
+            .as_ref()
+            .map(|w| w.len())
+            .or_else(|| self.positions.as_ref().map(|p| p.len()))
+    }
+
+    /// Number of dimensions of the positions, [None] if no (or zero) positions are given.
+    pub fn num_dimensions(&self) -> Option<usize> {
+        self.positions
+            .as_ref()
+            .and_then(|p| p.first())
+            .map(|p| p.len())
+    }
+
+    pub fn weights(&self) -> Option<&[f32]> {
+        self.weights.as_deref()
+    }
+
+    pub fn positions(&self) -> Option<&[Vec<f32>]> {
+        self.positions.as_deref()
+    }
+}
+
+impl Debug for NodeAttributes {
+    // The attributes hold a value per node, only the shape is printed.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NodeAttributes")
+            .field("weights", &self.weights.as_ref().map(|w| w.len()))
+            .field("positions", &self.positions.as_ref().map(|p| p.len()))
+            .field("dims", &self.num_dimensions())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::ParetoDistribution;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(1.5)]
+    #[case(f32::INFINITY)]
+    fn parameters_roundtrip_json(#[case] alpha: f32) {
+        let params = GenerationParametersBuilder::new()
+            .dimensions(1)
+            .weights(ParetoDistribution::new(1.0, 2.5))
+            .alpha(alpha)
+            .seed(7)
+            .tile_size(100)
+            .shard(1, 2)
+            .norm(Norm::Lp(3.0))
+            .model(Model::Hyperbolic(HyperbolicParameters {
+                radius: 10.0,
+                alpha: 0.75,
+                temperature: 0.5,
+            }))
+            .build()
+            .unwrap();
+
+        let json = serde_json::to_string(&params).unwrap();
+        let back: GenerationParameters<VecSeeds> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(back.seeds.seeds, params.seeds.seeds);
+        assert_eq!(back.seeds.master, Some(7));
+        assert_eq!(back.alpha, alpha);
+        assert_eq!(back.w, params.w);
+        assert_eq!(back.v, 1000);
+        assert_eq!(back.shard_index, 1);
+        assert_eq!(back.shard_count, 2);
+        assert_eq!(back.model, params.model);
+        assert_eq!(back.norm, params.norm);
+        assert_eq!(back.weights, params.weights);
+    }
+}
@@ -0,0 +1,411 @@
+//! Validation of [GenerationParameters].
+//!
+//! Invalid parameters make the generators panic, loop forever or silently produce garbage, so they are checked up front.
+//! [validate] reports every violated constraint at once, see [ValidationError].
+
+use crate::params::VecSeeds;
+use crate::random::WeightDistribution;
+use crate::MAX_DIMS;
+use generator_core::params::{EdgeModel, GenerationParameters, Norm, RandomMode};
+use std::fmt::{Display, Formatter};
+
+/// A single violated constraint of the [GenerationParameters].
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+pub enum ParameterError {
+    /// The graph has no dimensions.
+    #[error("Number of dimensions must be at least 1.")]
+    NoDimensions,
+    /// The [EdgeModel] requires a different number of dimensions, see [EdgeModel::required_dimensions].
+    #[error("The model requires exactly {required} dimensions, got {dims}.")]
+    ModelDimensions {
+        /// Number of dimensions of the graph.
+        dims: usize,
+        /// Number of dimensions the model requires.
+        required: usize,
+    },
+    /// The GPU kernel computes the positions on demand in arrays of [MAX_DIMS](crate::MAX_DIMS) entries.
+    #[error("On-demand GPU computation requires at most {max} dimensions, got {dims}. If more are needed, adjust MAX_DIMS in generator/core/src/lib.rs at the cost of GPU performance.")]
+    TooManyDimensions {
+        /// Number of dimensions of the graph.
+        dims: usize,
+        /// Largest supported number of dimensions.
+        max: usize,
+    },
+    /// The GPU kernel tests every pair of a tile, it cannot skip over them.
+    #[error("Skip sampling is not supported by the GPU generator.")]
+    GpuSkipSampling,
+    /// There must be one seed for the weights, one for the edges and one per dimension.
+    #[error("Expected {expected} seeds (one for the weights, one for the edges and one per dimension), got {got}.")]
+    SeedCount {
+        /// Number of seeds needed.
+        expected: usize,
+        /// Number of seeds given.
+        got: usize,
+    },
+    /// The tiles would be empty and the tile iterator would never end.
+    #[error("Tile size must be at least 1.")]
+    ZeroTileSize,
+    /// There has to be at least one shard.
+    #[error("Shard count must be at least 1.")]
+    NoShards,
+    /// The shard index is out of range.
+    #[error("Shard index {index} must be less than the shard count {count}.")]
+    ShardIndex {
+        /// Index of the shard.
+        index: usize,
+        /// Number of shards.
+        count: usize,
+    },
+    /// The alpha of the probability function must be greater than 1 (or infinite for the threshold model).
+    #[error("Alpha must be greater than 1, got {0}.")]
+    Alpha(f32),
+    /// The scaling constant must be positive.
+    #[error("Scaling constant c must be positive, got {0}.")]
+    Scaling(f32),
+    /// The exponent p of an [Lp](Norm::Lp) norm must be at least 1 to be a norm.
+    #[error("Exponent p of the norm must be at least 1, got {0}.")]
+    NormExponent(f32),
+    /// The weights must be positive.
+    #[error("x_min must be positive, got {0}.")]
+    XMin(f32),
+    /// The weights of the [discrete power law](WeightDistribution::DiscretePowerLaw) are integers, starting at x_min.
+    #[error("x_min of the discrete power law must be an integer, got {0}.")]
+    DiscreteXMin(f32),
+    /// The upper bound of bounded pareto weights must exceed the lower bound.
+    #[error("x_max must be greater than x_min, got x_max = {x_max} and x_min = {x_min}.")]
+    XMax {
+        /// Lower bound of the weights.
+        x_min: f32,
+        /// Upper bound of the weights.
+        x_max: f32,
+    },
+    /// The exponent of a power-law weight distribution is too small, the total weight would not be finite.
+    #[error("Exponent beta of the weight distribution must be greater than {min}, got {got}.")]
+    WeightExponent {
+        /// The exponent of the distribution.
+        got: f32,
+        /// The exponent has to be greater than this.
+        min: f32,
+    },
+    /// The standard deviation of log-normal weights must be non-negative.
+    #[error("Sigma of the log-normal weights must be non-negative, got {0}.")]
+    Sigma(f32),
+    /// The hyperbolic disk must have a positive, finite radius.
+    #[error("Radius of the hyperbolic disk must be positive and finite, got {0}.")]
+    HyperbolicRadius(f32),
+    /// The radial density of the hyperbolic model is only defined for a positive alpha_H.
+    #[error("alpha_H of the hyperbolic model must be positive, got {0}.")]
+    HyperbolicAlpha(f32),
+    /// A negative temperature would invert the edge probabilities of the hyperbolic model.
+    #[error("Temperature of the hyperbolic model must be non-negative, got {0}.")]
+    Temperature(f32),
+}
+
+/// All violated constraints of some [GenerationParameters].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationError {
+    /// The violated constraints, never empty.
+    pub errors: Vec<ParameterError>,
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid generation parameters:")?;
+        for e in self.errors.iter() {
+            write!(f, " {}", e)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl From<ParameterError> for ValidationError {
+    fn from(e: ParameterError) -> Self {
+        Self { errors: vec![e] }
+    }
+}
+
+/// The constraints on the parameters of the weight distribution.
+fn validate_weights(weights: &WeightDistribution, errors: &mut Vec<ParameterError>) {
+    let mut exponent = |got: f32, min: f32| {
+        if got.is_nan() || got <= min {
+            errors.push(ParameterError::WeightExponent { got, min });
+        }
+    };
+    let (x_min, x_max) = match *weights {
+        WeightDistribution::Pareto(dist) => {
+            exponent(dist.alpha, 1.0);
+            (Some(dist.x), None)
+        }
+        WeightDistribution::Constant(w) => (Some(w), None),
+        WeightDistribution::LogNormal { sigma, .. } => {
+            if sigma.is_nan() || sigma < 0.0 {
+                errors.push(ParameterError::Sigma(sigma));
+            }
+            (None, None)
+        }
+        WeightDistribution::BoundedPareto {
+            x_min,
+            x_max,
+            alpha,
+        } => {
+            // The upper bound keeps the total weight finite for any positive exponent.
+            exponent(alpha, 0.0);
+            (Some(x_min), Some(x_max))
+        }
+        WeightDistribution::DiscretePowerLaw { x_min, alpha } => {
+            exponent(alpha, 1.0);
+            if !x_min.is_nan() && x_min.fract() != 0.0 {
+                errors.push(ParameterError::DiscreteXMin(x_min));
+            }
+            (Some(x_min), None)
+        }
+    };
+
+    if let Some(x_min) = x_min {
+        if x_min.is_nan() || x_min <= 0.0 {
+            errors.push(ParameterError::XMin(x_min));
+        }
+        if let Some(x_max) = x_max {
+            if x_max.is_nan() || x_max <= x_min {
+                errors.push(ParameterError::XMax { x_min, x_max });
+            }
+        }
+    }
+}
+
+/// Checks all constraints on the parameters, returning every violated one.
+///
+/// The [W](GenerationParameters::w) of the parameters is not checked, so this can be called before it is computed.
+/// Constraints of specific generators are not checked, see [validate_gpu] for those of the GPU generator.
+pub fn validate<M: EdgeModel>(
+    params: &GenerationParameters<VecSeeds, M>,
+) -> Result<(), ValidationError> {
+    let mut errors = Vec::new();
+
+    let dims = params.num_dimensions();
+    if dims < 1 {
+        errors.push(ParameterError::NoDimensions);
+    }
+    if let Some(required) = params.model.required_dimensions() {
+        if dims != required {
+            errors.push(ParameterError::ModelDimensions { dims, required });
+        }
+    }
+    if params.seeds.seeds.len() != dims + 2 {
+        errors.push(ParameterError::SeedCount {
+            expected: dims + 2,
+            got: params.seeds.seeds.len(),
+        });
+    }
+
+    if params.tile_size == 0 {
+        errors.push(ParameterError::ZeroTileSize);
+    }
+    if params.shard_count == 0 {
+        errors.push(ParameterError::NoShards);
+    } else if params.shard_index >= params.shard_count {
+        errors.push(ParameterError::ShardIndex {
+            index: params.shard_index,
+            count: params.shard_count,
+        });
+    }
+
+    if params.alpha.is_nan() || params.alpha <= 1.0 {
+        errors.push(ParameterError::Alpha(params.alpha));
+    }
+    if params.c.is_nan() || params.c <= 0.0 {
+        errors.push(ParameterError::Scaling(params.c));
+    }
+    if let Norm::Lp(p) = params.norm {
+        if p.is_nan() || p < 1.0 {
+            errors.push(ParameterError::NormExponent(p));
+        }
+    }
+    validate_weights(&params.weights, &mut errors);
+    if let Some(h) = params.model.hyperbolic_parameters() {
+        if !h.radius.is_finite() || h.radius <= 0.0 {
+            errors.push(ParameterError::HyperbolicRadius(h.radius));
+        }
+        if h.alpha.is_nan() || h.alpha <= 0.0 {
+            errors.push(ParameterError::HyperbolicAlpha(h.alpha));
+        }
+        if h.temperature.is_nan() || h.temperature < 0.0 {
+            errors.push(ParameterError::Temperature(h.temperature));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationError { errors })
+    }
+}
+
+/// Checks the constraints of [validate] and those of the GPU generator, like [ParameterError::TooManyDimensions].
+///
+/// This is called before the GPU workers are started, so the run fails before any work is done.
+pub fn validate_gpu<M: EdgeModel>(
+    params: &GenerationParameters<VecSeeds, M>,
+) -> Result<(), ValidationError> {
+    let mut errors = match validate(params) {
+        Ok(()) => Vec::new(),
+        Err(e) => e.errors,
+    };
+
+    if params.num_dimensions() > MAX_DIMS && params.random_mode != RandomMode::PreGenerate {
+        errors.push(ParameterError::TooManyDimensions {
+            dims: params.num_dimensions(),
+            max: MAX_DIMS,
+        });
+    }
+    if params.skip_sampling {
+        errors.push(ParameterError::GpuSkipSampling);
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationError { errors })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::{GenerationParametersBuilder, HyperbolicParameters, Model};
+    use crate::random::ParetoDistribution;
+    use rstest::rstest;
+
+    fn params() -> GenerationParameters<VecSeeds> {
+        GenerationParametersBuilder::new()
+            .vertices(100)
+            .seeds(&[1, 2, 3, 4])
+            .tile_size(10)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert_eq!(validate(&params()), Ok(()));
+
+        let mut threshold = params();
+        threshold.alpha = f32::INFINITY;
+        assert_eq!(validate(&threshold), Ok(()));
+    }
+
+    /// Switches to a hyperbolic model with the given parameters and the single dimension it requires.
+    fn hyperbolic(p: &mut GenerationParameters<VecSeeds>, h: HyperbolicParameters) {
+        p.dims = 1;
+        p.seeds.seeds.pop();
+        p.model = Model::Hyperbolic(h);
+    }
+
+    #[rstest]
+    #[case::alpha(|p: &mut GenerationParameters<VecSeeds>| p.alpha = 1.0, ParameterError::Alpha(1.0))]
+    #[case::tile_size(|p: &mut GenerationParameters<VecSeeds>| p.tile_size = 0, ParameterError::ZeroTileSize)]
+    #[case::shard(
+        |p: &mut GenerationParameters<VecSeeds>| p.shard_index = 1,
+        ParameterError::ShardIndex { index: 1, count: 1 }
+    )]
+    #[case::beta(
+        |p: &mut GenerationParameters<VecSeeds>| p.weights = ParetoDistribution::new(1.0, 0.5).into(),
+        ParameterError::WeightExponent { got: 0.5, min: 1.0 }
+    )]
+    #[case::x_min(
+        |p: &mut GenerationParameters<VecSeeds>| p.weights = ParetoDistribution::new(0.0, 1.5).into(),
+        ParameterError::XMin(0.0)
+    )]
+    #[case::x_max(
+        |p: &mut GenerationParameters<VecSeeds>| p.weights = WeightDistribution::BoundedPareto { x_min: 2.0, x_max: 1.0, alpha: 1.5 },
+        ParameterError::XMax { x_min: 2.0, x_max: 1.0 }
+    )]
+    #[case::discrete_x_min(
+        |p: &mut GenerationParameters<VecSeeds>| p.weights = WeightDistribution::DiscretePowerLaw { x_min: 1.5, alpha: 1.5 },
+        ParameterError::DiscreteXMin(1.5)
+    )]
+    #[case::norm(|p: &mut GenerationParameters<VecSeeds>| p.norm = Norm::Lp(0.5), ParameterError::NormExponent(0.5))]
+    #[case::hyperbolic(
+        |p: &mut GenerationParameters<VecSeeds>| p.model = Model::Hyperbolic(HyperbolicParameters { radius: 5.0, alpha: 0.75, temperature: 0.0 }),
+        ParameterError::ModelDimensions { dims: 2, required: 1 }
+    )]
+    #[case::hyperbolic_radius(
+        |p: &mut GenerationParameters<VecSeeds>| hyperbolic(p, HyperbolicParameters { radius: 0.0, alpha: 0.75, temperature: 0.0 }),
+        ParameterError::HyperbolicRadius(0.0)
+    )]
+    #[case::hyperbolic_infinite_radius(
+        |p: &mut GenerationParameters<VecSeeds>| hyperbolic(p, HyperbolicParameters { radius: f32::NEG_INFINITY, alpha: 0.75, temperature: 0.0 }),
+        ParameterError::HyperbolicRadius(f32::NEG_INFINITY)
+    )]
+    #[case::hyperbolic_alpha(
+        |p: &mut GenerationParameters<VecSeeds>| hyperbolic(p, HyperbolicParameters { radius: 5.0, alpha: 0.0, temperature: 0.0 }),
+        ParameterError::HyperbolicAlpha(0.0)
+    )]
+    #[case::temperature(
+        |p: &mut GenerationParameters<VecSeeds>| hyperbolic(p, HyperbolicParameters { radius: 5.0, alpha: 0.75, temperature: -0.5 }),
+        ParameterError::Temperature(-0.5)
+    )]
+    fn single_violation(
+        #[case] change: fn(&mut GenerationParameters<VecSeeds>),
+        #[case] expected: ParameterError,
+    ) {
+        let mut p = params();
+        change(&mut p);
+        assert_eq!(validate(&p), Err(expected.into()));
+    }
+
+    #[test]
+    fn gpu_constraints() {
+        assert_eq!(validate_gpu(&params()), Ok(()));
+
+        let mut p = GenerationParametersBuilder::new()
+            .dimensions(MAX_DIMS + 1)
+            .vertices(100)
+            .seeds(&vec![1; MAX_DIMS + 3])
+            .tile_size(10)
+            .random_mode(RandomMode::OnDemand)
+            .skip_sampling(true)
+            .build()
+            .unwrap();
+        assert_eq!(validate(&p), Ok(()));
+        assert_eq!(
+            validate_gpu(&p).unwrap_err().errors,
+            vec![
+                ParameterError::TooManyDimensions {
+                    dims: MAX_DIMS + 1,
+                    max: MAX_DIMS
+                },
+                ParameterError::GpuSkipSampling
+            ]
+        );
+
+        // Pregenerated positions are not limited in their number of dimensions.
+        p.random_mode = RandomMode::PreGenerate;
+        p.skip_sampling = false;
+        assert_eq!(validate_gpu(&p), Ok(()));
+    }
+
+    #[test]
+    fn lists_every_violation() {
+        let mut p = params();
+        p.dims = 0;
+        p.tile_size = 0;
+        p.shard_count = 0;
+        p.alpha = f32::NAN;
+        let errors = validate(&p).unwrap_err().errors;
+        assert_eq!(errors.len(), 5, "{:?}", errors);
+        assert_eq!(errors[0], ParameterError::NoDimensions);
+        assert_eq!(
+            errors[1],
+            ParameterError::SeedCount {
+                expected: 2,
+                got: 4
+            }
+        );
+        assert_eq!(errors[2], ParameterError::ZeroTileSize);
+        assert_eq!(errors[3], ParameterError::NoShards);
+        assert!(matches!(errors[4], ParameterError::Alpha(a) if a.is_nan()));
+    }
+}
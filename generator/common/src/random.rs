@@ -10,7 +10,7 @@ pub fn generate_seeds(n: usize) -> Vec<u64> {
     for i in 0..n {
         loop {
             let r: u64 = rng.gen();
-            if (i == 0) || (!(seeds[0..(i - 1)].contains(&r))) {
+            if !seeds[0..i].contains(&r) {
                 seeds[i] = r;
                 break;
             }
@@ -19,3 +19,68 @@ pub fn generate_seeds(n: usize) -> Vec<u64> {
 
     seeds
 }
+
+/// Draws a random master seed, see [derive_seeds].
+pub fn generate_master_seed() -> u64 {
+    use rand::Rng;
+
+    rand::thread_rng().gen()
+}
+
+/// Derives `n` distinct seeds from a single master seed.
+///
+/// Seed k is the SplitMix64 hash of the master seed and the counter k, duplicates are skipped by moving on to the next counter.
+/// The derivation is part of the output format: changing it changes every graph generated from a master seed.
+pub fn derive_seeds(master: u64, n: usize) -> Vec<u64> {
+    let mut seeds = Vec::with_capacity(n);
+    let mut counter = 0u64;
+    while seeds.len() < n {
+        let s = splitmix64::splitmix64_2(master, counter);
+        if !seeds.contains(&s) {
+            seeds.push(s);
+        }
+        counter += 1;
+    }
+    seeds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(0)]
+    #[case(1)]
+    #[case(u64::MAX)]
+    fn derived_seeds_are_stable(#[case] master: u64) {
+        let seeds = derive_seeds(master, 6);
+        assert_eq!(seeds.len(), 6);
+        assert_eq!(seeds, derive_seeds(master, 6));
+        // Adding a dimension keeps the seeds of the others.
+        assert_eq!(seeds[..5], derive_seeds(master, 5)[..]);
+        for (k, s) in seeds.iter().enumerate() {
+            assert!(!seeds[..k].contains(s));
+        }
+    }
+
+    #[test]
+    fn derived_seeds_known_answer() {
+        assert_eq!(
+            derive_seeds(1, 3),
+            vec![
+                0x5e41_ab08_7439_611e,
+                0xe9fd_6049_d65a_f21e,
+                0xbcd9_dbb4_9673_066b
+            ]
+        );
+    }
+
+    #[test]
+    fn generated_seeds_are_distinct() {
+        let seeds = generate_seeds(16);
+        for (k, s) in seeds.iter().enumerate() {
+            assert!(!seeds[..k].contains(s));
+        }
+    }
+}
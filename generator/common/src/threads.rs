@@ -3,19 +3,22 @@ use crate::params::ext::GenerationParametersExt;
 use crate::params::VecSeeds;
 use crate::tiles::Tile;
 use crossbeam_channel::{Receiver, Sender};
-use generator_core::params::GenerationParameters;
+use generator_core::params::{EdgeModel, GenerationParameters};
 use std::thread::JoinHandle;
 use tracing::{info, instrument};
 
-pub fn start_generate_tiles_thread(
+pub fn start_generate_tiles_thread<M: EdgeModel + Send + 'static>(
     sender: Sender<Tile>,
-    params: &GenerationParameters<VecSeeds>,
+    params: &GenerationParameters<VecSeeds, M>,
 ) -> JoinHandle<()> {
     let params = params.clone();
     std::thread::spawn(move || generate_tiles(sender, &params))
 }
 
-pub fn generate_tiles(sender: Sender<Tile>, params: &GenerationParameters<VecSeeds>) {
+pub fn generate_tiles<M: EdgeModel>(
+    sender: Sender<Tile>,
+    params: &GenerationParameters<VecSeeds, M>,
+) {
     info!("Emitting tiles...");
 
     for tile in params.tiles() {
@@ -25,14 +28,14 @@ pub fn generate_tiles(sender: Sender<Tile>, params: &GenerationParameters<VecSee
     info!("Tiles are generated!");
 }
 
-pub fn start_workers<T: GraphGenerator>(
+pub fn start_workers<T: GraphGenerator<M>, M: EdgeModel + Send + 'static>(
     construct_arg: T::ConstructArgument,
     num_workers: usize,
     sender: EdgeSender,
     finisher: Sender<Tile>,
     receiver: Receiver<Tile>,
-    params: &GenerationParameters<VecSeeds>,
-) -> Vec<JoinHandle<()>> {
+    params: &GenerationParameters<VecSeeds, M>,
+) -> Vec<JoinHandle<anyhow::Result<()>>> {
     let mut handles = Vec::new();
 
     for i in 0u64..(num_workers as u64) {
@@ -42,7 +45,7 @@ pub fn start_workers<T: GraphGenerator>(
         let params = params.clone();
         let construct_arg = construct_arg.clone();
         handles.push(std::thread::spawn(move || {
-            worker_thread::<T>(i, construct_arg, sender, finisher, receiver, &params);
+            worker_thread::<T, M>(i, construct_arg, sender, finisher, receiver, &params)
         }));
     }
 
@@ -53,19 +56,21 @@ pub fn start_workers<T: GraphGenerator>(
     handles
 }
 
+/// Constructs a generator and runs it on the received tiles until the tile channel closes.
+///
+/// Errors of the generator are returned, [start_workers] hands them out through the join handles and [generate_edges](crate::edges::generate_edges) forwards them.
 #[instrument(skip_all, fields(tid = _thread_id))]
-pub fn worker_thread<T: GraphGenerator>(
+pub fn worker_thread<T: GraphGenerator<M>, M: EdgeModel>(
     _thread_id: u64,
     construct_arg: T::ConstructArgument,
     sender: EdgeSender,
     finisher: Sender<Tile>,
     receiver: Receiver<Tile>,
-    params: &GenerationParameters<VecSeeds>,
-) {
+    params: &GenerationParameters<VecSeeds, M>,
+) -> anyhow::Result<()> {
     info!("Running!");
-    let generator = T::new(construct_arg).unwrap();
-    generator
-        .generate(sender, finisher, receiver, params)
-        .unwrap();
+    let generator = T::new(construct_arg)?;
+    generator.generate(sender, finisher, receiver, params)?;
     info!("Thread exit.");
+    Ok(())
 }
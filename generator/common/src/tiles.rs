@@ -2,6 +2,8 @@
 pub struct TilesIterator {
     vertices: u64,
     tile_size: u64,
+    /// Only emit the tiles on or above the diagonal (those that contain pairs with i < j).
+    upper_triangle: bool,
     i: u64,
     j: u64,
 }
@@ -11,10 +13,29 @@ impl TilesIterator {
         TilesIterator {
             vertices: max,
             tile_size: step,
+            upper_triangle: false,
             i: 0u64,
             j: 0u64,
         }
     }
+
+    /// Iterator over only the tiles on or above the diagonal, as used for undirected graphs.
+    pub fn new_upper_triangle(max: u64, step: u64) -> Self {
+        TilesIterator {
+            upper_triangle: true,
+            ..Self::new(max, step)
+        }
+    }
+
+    /// Number of tiles this iterator emits in total.
+    pub fn count_tiles(vertices: u64, tile_size: u64, upper_triangle: bool) -> u64 {
+        let n = num_integer::div_ceil(vertices, tile_size);
+        if upper_triangle {
+            n * (n + 1) / 2
+        } else {
+            n.pow(2)
+        }
+    }
 }
 
 pub type Tile = ((u64, u64), (u64, u64));
@@ -32,7 +53,7 @@ impl Iterator for TilesIterator {
             let next = ((self.i, self.j), (i_next, j_next));
 
             self.i = i_next;
-            if self.i >= self.vertices {
+            if self.i >= self.vertices || (self.upper_triangle && self.i >= j_next) {
                 self.j = j_next;
                 self.i = 0;
             }
@@ -66,4 +87,45 @@ mod tests {
         let max_y: u64 = iter.map(|((_, _), (_, x))| x).max().unwrap();
         assert_eq!(max_y, vertices, "testing max_x");
     }
+
+    #[rstest]
+    #[case(10000, 2000)]
+    #[case(10000, 3000)]
+    #[case(10000, 1999)]
+    #[case(9999, 2001)]
+    fn upper_triangle_works(#[case] vertices: u64, #[case] tile_size: u64) {
+        let n = num_integer::div_ceil(vertices, tile_size);
+        let exp = n * (n + 1) / 2;
+        let iter = TilesIterator::new_upper_triangle(vertices, tile_size);
+        let act = iter.clone().count() as u64;
+        assert_eq!(act, exp, "testing amount of tiles");
+        assert_eq!(
+            TilesIterator::count_tiles(vertices, tile_size, true),
+            exp,
+            "testing count_tiles"
+        );
+
+        for ((i, j), (_, j_end)) in iter {
+            assert!(i < j_end, "tile ({}, {}) is below the diagonal", i, j);
+        }
+    }
+
+    #[test]
+    fn upper_triangle_covers_all_pairs_once() {
+        let (vertices, tile_size) = (23, 5);
+        let mut seen = vec![0usize; (vertices * vertices) as usize];
+        for ((si, sj), (ei, ej)) in TilesIterator::new_upper_triangle(vertices, tile_size) {
+            for j in sj..ej {
+                for i in si..ei {
+                    seen[(i * vertices + j) as usize] += 1;
+                }
+            }
+        }
+
+        for i in 0..vertices {
+            for j in (i + 1)..vertices {
+                assert_eq!(seen[(i * vertices + j) as usize], 1, "pair ({}, {})", i, j);
+            }
+        }
+    }
 }
@@ -5,40 +5,42 @@
 #[allow(unused_imports)]
 use cuda_std::GpuFloat;
 
-use crate::params::{GenerationParameters, SeedEnum, SeedGettable};
+use crate::model::{EdgeModel, HyperbolicParameters};
+use crate::params::{
+    EdgeRandomness, GenerationParameters, Geometry, GraphKind, Norm, SeedEnum, SeedGettable,
+};
 use crate::random;
-use no_std_compat::cmp::Ordering::Equal;
+use core::f64::consts::PI;
 
-/// Computes the distance between two positions of an d-dimensional torus.
+/// Computes the distance between two positions of an d-dimensional torus or cube (see [Geometry]).
 ///
+/// The per-dimension distances are combined using the given [Norm].
 /// This function assumes the length of the slices is equal to the number of dimensions and that both slices are of equal length.
-pub fn compute_distance(p_i: &[f32], p_j: &[f32]) -> f32 {
-    fn dist_c(i: f32, j: f32) -> f32 {
-        (i - j).abs().min(1.0f32 - ((i - j).abs()))
-    }
-    p_i.iter()
-        .zip(p_j.iter())
-        .map(|(p0, p1)| dist_c(*p0, *p1))
-        .max_by(|a, b| a.partial_cmp(b).unwrap_or(Equal))
-        .unwrap()
+pub fn compute_distance(p_i: &[f32], p_j: &[f32], norm: Norm, geometry: Geometry) -> f32 {
+    norm.finish(p_i.iter().zip(p_j.iter()).fold(0.0f32, |acc, (p0, p1)| {
+        norm.accumulate(acc, geometry.delta(*p0, *p1))
+    }))
 }
 
 /// Actual probability function.
 ///
+/// This is `min(1, c (w_i w_j / W)^alpha / d^(alpha d))` for the soft model.
+/// For an infinite alpha this is the threshold model, an edge exists iff `d <= (c w_i w_j / W)^(1/d)`.
+///
 /// # Arguments
 /// * `d` - Distance between nodes i and j.
 /// * `w_i` - Weight of node i.
 /// * `w_j` - Weight of node j.
 /// * `params` - Reference to the parameters for the graph being generated. See [GenerationParameters].
-pub fn compute_probability<S: SeedGettable>(
+pub fn compute_probability<S: SeedGettable, M: EdgeModel>(
     // Distance between nodes i and j.
     d: f32,
     w_i: f32,
     w_j: f32,
-    params: &GenerationParameters<S>,
+    params: &GenerationParameters<S, M>,
 ) -> f32 {
     if params.alpha.is_infinite() {
-        let v = ((w_i * w_j) / params.w).powf(1.0f32 / params.num_dimensions() as f32);
+        let v = (params.c * (w_i * w_j) / params.w).powf(1.0f32 / params.num_dimensions() as f32);
         if d <= v {
             1.0f32
         } else {
@@ -47,7 +49,7 @@ pub fn compute_probability<S: SeedGettable>(
     } else {
         (
             //The main multiplication
-            (((w_i * w_j) / params.w).powf(params.alpha))
+            params.c * (((w_i * w_j) / params.w).powf(params.alpha))
                 // 1/dist^(ad)
                 / (d.powf(params.alpha * params.num_dimensions() as f32))
         )
@@ -55,8 +57,64 @@ pub fn compute_probability<S: SeedGettable>(
     }
 }
 
+/// Chung-Lu probability function, `min(1, c w_i w_j / W)`.
+///
+/// # Arguments
+/// * `w_i` - Weight of node i.
+/// * `w_j` - Weight of node j.
+/// * `params` - Reference to the parameters for the graph being generated. See [GenerationParameters].
+pub fn compute_chung_lu_probability<S: SeedGettable, M: EdgeModel>(
+    w_i: f32,
+    w_j: f32,
+    params: &GenerationParameters<S, M>,
+) -> f32 {
+    (params.c * (w_i * w_j) / params.w).min(1.0f32)
+}
+
+/// Computes the hyperbolic distance between two nodes in the native representation of the hyperbolic disk.
+///
+/// # Arguments
+/// * `r_i` - Radial coordinate of node i.
+/// * `r_j` - Radial coordinate of node j.
+/// * `p_i` - Position of node i, the first dimension holds the angle as a fraction of a full turn.
+/// * `p_j` - Position of node j, the first dimension holds the angle as a fraction of a full turn.
+pub fn compute_hyperbolic_distance(r_i: f32, r_j: f32, p_i: &[f32], p_j: &[f32]) -> f32 {
+    let (r_i, r_j) = (r_i as f64, r_j as f64);
+    // Angle between the nodes in [0, pi].
+    let theta = 2.0f64 * PI * (Geometry::Torus.delta(p_i[0], p_j[0]) as f64);
+    // cosh(d) = cosh(r_i)cosh(r_j) - sinh(r_i)sinh(r_j)cos(theta), rewritten to avoid cancellation for small angles.
+    let s = (theta / 2.0f64).sin();
+    let cosh_d = (r_i - r_j).cosh() + 2.0f64 * s * s * r_i.sinh() * r_j.sinh();
+    cosh_d.max(1.0f64).acosh() as f32
+}
+
+/// Probability function of the hyperbolic random graph model.
+///
+/// For a temperature of 0 this is the threshold model, nodes are connected iff their distance is at most R.
+/// Otherwise the probability is `1 / (1 + exp((d - R) / 2T))`.
+///
+/// # Arguments
+/// * `d` - Hyperbolic distance between nodes i and j. See [compute_hyperbolic_distance].
+/// * `params` - The parameters of the hyperbolic model.
+pub fn compute_hyperbolic_probability(d: f32, params: &HyperbolicParameters) -> f32 {
+    if params.temperature == 0.0f32 {
+        if d <= params.radius {
+            1.0f32
+        } else {
+            0.0f32
+        }
+    } else {
+        1.0f32 / (1.0f32 + ((d - params.radius) / (2.0f32 * params.temperature)).exp())
+    }
+}
+
 /// Function that determines whether an edge exists.
 ///
+/// Pairs that are not candidates for the [GraphKind] of the graph (see [GenerationParameters::is_candidate_pair]) never produce an edge.
+/// For [GraphKind::Undirected] the random value of the pair is symmetric in i and j.
+///
+/// The probability of the edge is given by the [EdgeModel] of the graph.
+///
 /// # Arguments
 /// * `i` - Left node index.
 /// * `j` - Right node index.
@@ -65,18 +123,222 @@ pub fn compute_probability<S: SeedGettable>(
 /// * `p_i` - Position of node i.
 /// * `p_j` - Position of node j.
 /// * `params` - Reference to the parameters for the graph being generated. See [GenerationParameters].
-pub fn generate_edge<S: SeedGettable>(
+pub fn generate_edge<S: SeedGettable, M: EdgeModel>(
     i: u64,
     j: u64,
     w_i: f32,
     w_j: f32,
     p_i: &[f32],
     p_j: &[f32],
-    params: &GenerationParameters<S>,
+    params: &GenerationParameters<S, M>,
 ) -> bool {
-    let d = compute_distance(p_i, p_j);
-    let p = compute_probability(d, w_i, w_j, params);
-    let rp = random::random_edge(i, j, params.get_seed(SeedEnum::Edge));
+    if !params.is_candidate_pair(i, j) {
+        return false;
+    }
+
+    let p = params.model.probability(w_i, w_j, p_i, p_j, params);
+    let seed = params.get_seed(SeedEnum::Edge);
+    match (params.edge_randomness, params.graph_kind) {
+        (EdgeRandomness::Bits32, GraphKind::Directed) => {
+            p > random::random_edge(&params.hash, i, j, seed)
+        }
+        (EdgeRandomness::Bits32, GraphKind::Undirected) => {
+            p > random::random_edge_symmetric(&params.hash, i, j, seed)
+        }
+        (EdgeRandomness::Bits64, GraphKind::Directed) => {
+            p as f64 > random::random_edge_64(&params.hash, i, j, seed)
+        }
+        (EdgeRandomness::Bits64, GraphKind::Undirected) => {
+            p as f64 > random::random_edge_symmetric_64(&params.hash, i, j, seed)
+        }
+    }
+}
 
-    p > rp
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand_xoshiro::rand_core::SeedableRng;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    /// Straightforward f64 implementation of the torus distance under the p-norm (p = infinity for the max-norm).
+    fn reference_distance(p_i: &[f32], p_j: &[f32], p: f64) -> f64 {
+        let deltas = p_i.iter().zip(p_j.iter()).map(|(a, b)| {
+            let d = ((*a as f64) - (*b as f64)).abs();
+            d.min(1.0 - d)
+        });
+        reference_norm(deltas, p)
+    }
+
+    fn reference_norm<I: Iterator<Item = f64>>(deltas: I, p: f64) -> f64 {
+        if p.is_infinite() {
+            deltas.fold(0.0, f64::max)
+        } else {
+            deltas.map(|d| d.powf(p)).sum::<f64>().powf(1.0 / p)
+        }
+    }
+
+    #[test]
+    fn norms_match_reference() {
+        let norms = [
+            (Norm::L1, 1.0),
+            (Norm::L2, 2.0),
+            (Norm::Lp(3.0), 3.0),
+            (Norm::Lp(1.5), 1.5),
+            (Norm::Max, f64::INFINITY),
+        ];
+        for (norm, p) in norms {
+            check_norm(norm, p);
+        }
+    }
+
+    fn check_norm(norm: Norm, p: f64) {
+        let mut rng = Xoshiro256StarStar::seed_from_u64(0);
+
+        for dims in 1..=4 {
+            for _ in 0..1024 {
+                let mut p_i = [0.0f32; 4];
+                let mut p_j = [0.0f32; 4];
+                for d in 0..dims {
+                    p_i[d] = rng.gen();
+                    p_j[d] = rng.gen();
+                }
+
+                let expected = reference_distance(&p_i[..dims], &p_j[..dims], p);
+                let actual =
+                    compute_distance(&p_i[..dims], &p_j[..dims], norm, Geometry::Torus) as f64;
+                assert!(
+                    (actual - expected).abs() <= 1e-5,
+                    "{:?} distance of {:?} and {:?}: {} != {}",
+                    norm,
+                    &p_i[..dims],
+                    &p_j[..dims],
+                    actual,
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn cube_does_not_wrap() {
+        let p_i = [0.05f32, 0.5];
+        let p_j = [0.95f32, 0.5];
+
+        let torus = compute_distance(&p_i, &p_j, Norm::Max, Geometry::Torus);
+        let cube = compute_distance(&p_i, &p_j, Norm::Max, Geometry::Cube);
+        assert!((torus - 0.1).abs() < 1e-6, "torus distance {}", torus);
+        assert!((cube - 0.9).abs() < 1e-6, "cube distance {}", cube);
+    }
+
+    #[test]
+    fn cube_matches_reference() {
+        let mut rng = Xoshiro256StarStar::seed_from_u64(1);
+
+        for _ in 0..1024 {
+            let p_i: [f32; 3] = [rng.gen(), rng.gen(), rng.gen()];
+            let p_j: [f32; 3] = [rng.gen(), rng.gen(), rng.gen()];
+
+            let deltas = p_i
+                .iter()
+                .zip(p_j.iter())
+                .map(|(a, b)| ((*a as f64) - (*b as f64)).abs());
+            let expected = reference_norm(deltas, 2.0);
+            let cube = compute_distance(&p_i, &p_j, Norm::L2, Geometry::Cube);
+            let torus = compute_distance(&p_i, &p_j, Norm::L2, Geometry::Torus);
+            assert!(((cube as f64) - expected).abs() <= 1e-5);
+            assert!(cube >= torus, "the torus never has larger distances");
+        }
+    }
+
+    #[test]
+    fn hyperbolic_distance_matches_reference() {
+        let mut rng = Xoshiro256StarStar::seed_from_u64(2);
+
+        for _ in 0..1024 {
+            let (r_i, r_j): (f32, f32) = (rng.gen::<f32>() * 20.0, rng.gen::<f32>() * 20.0);
+            let (a_i, a_j): (f32, f32) = (rng.gen(), rng.gen());
+
+            // The textbook hyperbolic law of cosines.
+            let (r_i64, r_j64) = (r_i as f64, r_j as f64);
+            let theta = 2.0 * PI * ((a_i as f64) - (a_j as f64));
+            let expected = (r_i64.cosh() * r_j64.cosh()
+                - r_i64.sinh() * r_j64.sinh() * theta.cos())
+            .max(1.0)
+            .acosh();
+
+            let actual = compute_hyperbolic_distance(r_i, r_j, &[a_i], &[a_j]) as f64;
+            assert!(
+                (actual - expected).abs() <= 1e-3 * expected.max(1.0),
+                "distance between ({}, {}) and ({}, {}): {} != {}",
+                r_i,
+                a_i,
+                r_j,
+                a_j,
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn hyperbolic_probability() {
+        let threshold = HyperbolicParameters {
+            radius: 10.0,
+            alpha: 0.75,
+            temperature: 0.0,
+        };
+        assert_eq!(compute_hyperbolic_probability(9.9, &threshold), 1.0);
+        assert_eq!(compute_hyperbolic_probability(10.1, &threshold), 0.0);
+
+        let soft = HyperbolicParameters {
+            temperature: 0.5,
+            ..threshold
+        };
+        assert!((compute_hyperbolic_probability(10.0, &soft) - 0.5).abs() < 1e-6);
+        assert!(compute_hyperbolic_probability(5.0, &soft) > 0.99);
+        assert!(compute_hyperbolic_probability(15.0, &soft) < 0.01);
+    }
+
+    #[test]
+    fn hyperbolic_radius_distribution() {
+        let params = HyperbolicParameters {
+            radius: 12.0,
+            alpha: 0.75,
+            temperature: 0.0,
+        };
+        let cdf = |r: f64| {
+            let a = params.alpha as f64;
+            ((a * r).cosh() - 1.0) / ((a * params.radius as f64).cosh() - 1.0)
+        };
+
+        let mut rng = Xoshiro256StarStar::seed_from_u64(3);
+        let n = 100_000;
+        let mut below = [0usize; 4];
+        let cutoffs = [6.0, 9.0, 10.5, 11.5];
+        for _ in 0..n {
+            let r = random::uniform_to_hyperbolic_radius(rng.gen(), &params);
+            assert!(
+                (0.0..=params.radius).contains(&r),
+                "radius {} out of range",
+                r
+            );
+            for (c, b) in cutoffs.iter().zip(below.iter_mut()) {
+                if (r as f64) <= *c {
+                    *b += 1;
+                }
+            }
+        }
+
+        for (c, b) in cutoffs.iter().zip(below.iter()) {
+            let empirical = *b as f64 / n as f64;
+            assert!(
+                (empirical - cdf(*c)).abs() < 0.01,
+                "P(r <= {}) = {}, expected {}",
+                c,
+                empirical,
+                cdf(*c)
+            );
+        }
+    }
 }
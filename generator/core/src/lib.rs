@@ -1,7 +1,7 @@
 //! The core of the generation algorithm.
 //!
 //! This crate implements the core functionality for the generation algorithm.
-//! Notably, this crate contains the probability function itself.
+//! Notably, this crate contains the probability function itself and the [EdgeModel](model::EdgeModel) trait to plug in new ones.
 #![warn(missing_docs)]
 #![warn(clippy::missing_docs_in_private_items)]
 #![cfg_attr(
@@ -13,6 +13,7 @@
 #![allow(clippy::missing_safety_doc)]
 
 pub mod algorithm;
+pub mod model;
 pub mod params;
 pub mod random;
 pub mod memory;
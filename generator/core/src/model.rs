@@ -0,0 +1,321 @@
+//! The edge model module.
+//!
+//! An edge model decides how the weights of the nodes are sampled and what the probability of an edge between two nodes is.
+//! Everything else (the positions, the tiles, the randomness deciding whether an edge exists) is shared by all models.
+//!
+//! New models can be plugged in by implementing [EdgeModel] and using it as the model type of [GenerationParameters].
+//! The [Model] enum bundles the built-in models so they can be selected at run-time, it is the default model type.
+
+//This import isn't actually unused, the compiler just gets confused.
+//It's needed for float intrinsics.
+#[allow(unused_imports)]
+use cuda_std::GpuFloat;
+
+use crate::algorithm::{
+    compute_chung_lu_probability, compute_distance, compute_hyperbolic_distance,
+    compute_hyperbolic_probability, compute_probability,
+};
+use crate::params::{GenerationParameters, SeedGettable};
+use crate::random;
+
+/// A probability kernel of a random graph model.
+///
+/// Implementations must be [Copy] as they are part of the [GenerationParameters].
+pub trait EdgeModel: Copy + core::fmt::Debug {
+    /// Maps a uniform number in `[0, 1)` to the weight of a node.
+    ///
+    /// The default samples from the [weight distribution](GenerationParameters::weights) of the graph.
+    fn sample_weight<S: SeedGettable>(
+        &self,
+        u: f32,
+        params: &GenerationParameters<S, Self>,
+    ) -> f32 {
+        params.weights.sample(u)
+    }
+
+    /// The probability of an edge between nodes i and j.
+    ///
+    /// # Arguments
+    /// * `w_i` - Weight of node i.
+    /// * `w_j` - Weight of node j.
+    /// * `p_i` - Position of node i.
+    /// * `p_j` - Position of node j.
+    /// * `params` - Reference to the parameters for the graph being generated. See [GenerationParameters].
+    fn probability<S: SeedGettable>(
+        &self,
+        w_i: f32,
+        w_j: f32,
+        p_i: &[f32],
+        p_j: &[f32],
+        params: &GenerationParameters<S, Self>,
+    ) -> f32;
+
+    /// An upper bound on the probability of an edge between nodes with weights at most `w_i` and `w_j` at a distance of at least `d`.
+    ///
+    /// Samplers that skip over unlikely pairs (like the BKL generator of the CPU backend) need this bound.
+    /// Returns [None] if the model can not provide one, which is the default.
+    fn probability_bound<S: SeedGettable>(
+        &self,
+        _w_i: f32,
+        _w_j: f32,
+        _d: f32,
+        _params: &GenerationParameters<S, Self>,
+    ) -> Option<f32> {
+        None
+    }
+
+    /// The number of dimensions the model requires, [None] if it works with any number, which is the default.
+    fn required_dimensions(&self) -> Option<usize> {
+        None
+    }
+
+    /// Whether [probability](Self::probability) is the one of [Girg], which lets backends use kernels specialized for it.
+    ///
+    /// The default is `false`.
+    fn is_girg(&self) -> bool {
+        false
+    }
+
+    /// The parameters of a hyperbolic random graph model, so they can be validated.
+    ///
+    /// The default is [None].
+    fn hyperbolic_parameters(&self) -> Option<HyperbolicParameters> {
+        None
+    }
+}
+
+/// Geometric inhomogeneous random graph, see [compute_probability].
+///
+/// This is the soft model for a finite [alpha](GenerationParameters::alpha) and the threshold model for an infinite one.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "gpu"),
+    derive(cust::DeviceCopy)
+)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Girg;
+
+impl EdgeModel for Girg {
+    fn probability<S: SeedGettable>(
+        &self,
+        w_i: f32,
+        w_j: f32,
+        p_i: &[f32],
+        p_j: &[f32],
+        params: &GenerationParameters<S, Self>,
+    ) -> f32 {
+        let d = compute_distance(p_i, p_j, params.norm, params.geometry);
+        compute_probability(d, w_i, w_j, params)
+    }
+
+    fn probability_bound<S: SeedGettable>(
+        &self,
+        w_i: f32,
+        w_j: f32,
+        d: f32,
+        params: &GenerationParameters<S, Self>,
+    ) -> Option<f32> {
+        // The probability increases with the weights and decreases with the distance.
+        Some(compute_probability(d, w_i, w_j, params))
+    }
+
+    fn is_girg(&self) -> bool {
+        true
+    }
+}
+
+/// Chung-Lu random graph, nodes are connected with probability `min(1, c w_i w_j / W)`.
+///
+/// The positions of the nodes are ignored.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "gpu"),
+    derive(cust::DeviceCopy)
+)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct ChungLu;
+
+impl EdgeModel for ChungLu {
+    fn probability<S: SeedGettable>(
+        &self,
+        w_i: f32,
+        w_j: f32,
+        _p_i: &[f32],
+        _p_j: &[f32],
+        params: &GenerationParameters<S, Self>,
+    ) -> f32 {
+        compute_chung_lu_probability(w_i, w_j, params)
+    }
+
+    fn probability_bound<S: SeedGettable>(
+        &self,
+        w_i: f32,
+        w_j: f32,
+        _d: f32,
+        params: &GenerationParameters<S, Self>,
+    ) -> Option<f32> {
+        Some(compute_chung_lu_probability(w_i, w_j, params))
+    }
+}
+
+/// Native parameters of the (soft) hyperbolic random graph model.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "gpu"),
+    derive(cust::DeviceCopy)
+)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct HyperbolicParameters {
+    /// Radius R of the hyperbolic disk the nodes are placed in.
+    pub radius: f32,
+    /// Curvature parameter alpha_H, controls the radial density of the nodes.
+    pub alpha: f32,
+    /// Temperature T. A temperature of 0 yields the threshold model.
+    pub temperature: f32,
+}
+
+/// Hyperbolic random graph, see [compute_hyperbolic_probability].
+///
+/// In this model the weight of a node is its radial coordinate and the first dimension of its position is its angle (as a fraction of a full turn).
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "gpu"),
+    derive(cust::DeviceCopy)
+)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Hyperbolic(pub HyperbolicParameters);
+
+impl EdgeModel for Hyperbolic {
+    fn sample_weight<S: SeedGettable>(
+        &self,
+        u: f32,
+        _params: &GenerationParameters<S, Self>,
+    ) -> f32 {
+        random::uniform_to_hyperbolic_radius(u, &self.0)
+    }
+
+    fn probability<S: SeedGettable>(
+        &self,
+        w_i: f32,
+        w_j: f32,
+        p_i: &[f32],
+        p_j: &[f32],
+        _params: &GenerationParameters<S, Self>,
+    ) -> f32 {
+        compute_hyperbolic_probability(compute_hyperbolic_distance(w_i, w_j, p_i, p_j), &self.0)
+    }
+
+    /// The single dimension holds the angle of a node.
+    fn required_dimensions(&self) -> Option<usize> {
+        Some(1)
+    }
+
+    fn hyperbolic_parameters(&self) -> Option<HyperbolicParameters> {
+        Some(self.0)
+    }
+}
+
+/// The built-in models, selectable at run-time.
+///
+/// This is the default model type of [GenerationParameters] and the only one supported by the [GPU generator](generator_gpu).
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "gpu"),
+    derive(cust::DeviceCopy)
+)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Model {
+    /// See [Girg].
+    Girg,
+    /// See [ChungLu].
+    ChungLu,
+    /// See [Hyperbolic].
+    Hyperbolic(HyperbolicParameters),
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Self::Girg
+    }
+}
+
+impl EdgeModel for Model {
+    fn sample_weight<S: SeedGettable>(
+        &self,
+        u: f32,
+        params: &GenerationParameters<S, Self>,
+    ) -> f32 {
+        match self {
+            Model::Girg | Model::ChungLu => params.weights.sample(u),
+            Model::Hyperbolic(h) => random::uniform_to_hyperbolic_radius(u, h),
+        }
+    }
+
+    fn probability<S: SeedGettable>(
+        &self,
+        w_i: f32,
+        w_j: f32,
+        p_i: &[f32],
+        p_j: &[f32],
+        params: &GenerationParameters<S, Self>,
+    ) -> f32 {
+        match self {
+            Model::Girg => {
+                let d = compute_distance(p_i, p_j, params.norm, params.geometry);
+                compute_probability(d, w_i, w_j, params)
+            }
+            Model::ChungLu => compute_chung_lu_probability(w_i, w_j, params),
+            Model::Hyperbolic(h) => {
+                compute_hyperbolic_probability(compute_hyperbolic_distance(w_i, w_j, p_i, p_j), h)
+            }
+        }
+    }
+
+    fn probability_bound<S: SeedGettable>(
+        &self,
+        w_i: f32,
+        w_j: f32,
+        d: f32,
+        params: &GenerationParameters<S, Self>,
+    ) -> Option<f32> {
+        match self {
+            Model::Girg => Some(compute_probability(d, w_i, w_j, params)),
+            Model::ChungLu => Some(compute_chung_lu_probability(w_i, w_j, params)),
+            // The distance is not measured on the torus, so the cells give no bound.
+            Model::Hyperbolic(_) => None,
+        }
+    }
+
+    fn required_dimensions(&self) -> Option<usize> {
+        match self {
+            Model::Girg | Model::ChungLu => None,
+            Model::Hyperbolic(h) => Hyperbolic(*h).required_dimensions(),
+        }
+    }
+
+    fn is_girg(&self) -> bool {
+        matches!(self, Model::Girg)
+    }
+
+    fn hyperbolic_parameters(&self) -> Option<HyperbolicParameters> {
+        match self {
+            Model::Girg | Model::ChungLu => None,
+            Model::Hyperbolic(h) => Some(*h),
+        }
+    }
+}
@@ -2,10 +2,16 @@
 //!
 //! See [GenerationParameters] for the explanation of how this is structured.
 
+//This import isn't actually unused, the compiler just gets confused.
+//It's needed for float intrinsics.
+#[allow(unused_imports)]
+use cuda_std::GpuFloat;
 use no_std_compat::ops::Div;
 use no_std_compat::prelude::v1::*;
 
 use super::random;
+pub use crate::model::{EdgeModel, HyperbolicParameters, Model};
+pub use crate::random::hash::{CounterHash, HashFunction};
 
 #[derive(Clone, Copy, Eq, Ord, PartialOrd, PartialEq)]
 pub enum SeedEnum {
@@ -44,11 +50,150 @@ impl SeedGettable for RawSeeds {
     }
 }
 
+/// The kind of graph that is generated.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "gpu"),
+    derive(cust::DeviceCopy)
+)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum GraphKind {
+    /// Every ordered pair (i, j) is a candidate edge, including the self-pairs (i, i).
+    Directed,
+    /// Only pairs with i < j are candidate edges, which yields a simple undirected graph.
+    ///
+    /// Only the tiles on or above the diagonal are scheduled in this mode.
+    Undirected,
+}
+
+/// How the generators obtain the weights and positions of the nodes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "gpu"),
+    derive(cust::DeviceCopy)
+)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum RandomMode {
+    /// Computed once for all nodes before the edges are generated, the memory is proportional to the number of vertices.
+    PreGenerate,
+    /// Computed for every pair, without extra memory.
+    OnDemand,
+    /// Computed once per tile for the rows and columns of the tile, the memory is proportional to the tile size.
+    ///
+    /// Only the CPU generators cache the tiles, the GPU generator computes the numbers on demand in this mode.
+    TileCached,
+}
+
+/// The randomness that decides whether a pair with a given probability is an edge.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "gpu"),
+    derive(cust::DeviceCopy)
+)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum EdgeRandomness {
+    /// A 32-bit hash, compared as f32.
+    ///
+    /// Probabilities are quantized to `2^-32`, so probabilities below about `2.3e-10` all behave the same.
+    Bits32,
+    /// A 64-bit hash, compared as f64.
+    ///
+    /// Probabilities down to `2^-64` are honored, at the cost of a slower hash.
+    Bits64,
+}
+
+/// The norm used to combine the per-dimension distances into a single distance.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "gpu"),
+    derive(cust::DeviceCopy)
+)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Norm {
+    /// Manhattan distance, the sum of the per-dimension distances.
+    L1,
+    /// Euclidean distance.
+    L2,
+    /// General p-norm, `(sum |x|^p)^(1/p)`.
+    Lp(f32),
+    /// Maximum norm, the largest per-dimension distance.
+    Max,
+}
+
+impl Norm {
+    /// Folds the distance `x` along one dimension into the accumulator `acc`.
+    ///
+    /// The accumulator starts at `0.0` and is turned into the distance with [Norm::finish].
+    pub fn accumulate(&self, acc: f32, x: f32) -> f32 {
+        match self {
+            Norm::L1 => acc + x,
+            Norm::L2 => acc + x * x,
+            Norm::Lp(p) => acc + x.powf(*p),
+            Norm::Max => acc.max(x),
+        }
+    }
+
+    /// Turns an accumulator produced by [Norm::accumulate] into the actual distance.
+    pub fn finish(&self, acc: f32) -> f32 {
+        match self {
+            Norm::L1 | Norm::Max => acc,
+            Norm::L2 => acc.sqrt(),
+            Norm::Lp(p) => acc.powf(1.0f32 / *p),
+        }
+    }
+}
+
+/// The space the positions of the nodes live in.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "gpu"),
+    derive(cust::DeviceCopy)
+)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Geometry {
+    /// The unit cube with periodic boundaries, distances wrap around.
+    Torus,
+    /// The unit cube with hard boundaries, distances do not wrap around.
+    ///
+    /// Nodes near the border have fewer nodes close to them and thus a lower expected degree.
+    Cube,
+}
+
+impl Geometry {
+    /// Distance between the coordinates `a` and `b` along a single dimension.
+    pub fn delta(&self, a: f32, b: f32) -> f32 {
+        let d = (a - b).abs();
+        match self {
+            Geometry::Torus => d.min(1.0f32 - d),
+            Geometry::Cube => d,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 #[cfg_attr(
     all(not(target_os = "cuda"), feature = "gpu"),
     derive(cust::DeviceCopy)
 )]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 /// This struct holds the main parameters for the graph being generated.
 ///
 /// An important requirement for this struct is that it is [Copy]-able.
@@ -62,22 +207,38 @@ impl SeedGettable for RawSeeds {
 ///
 /// But we cannot just copy such a [Vec<u64>] to the GPU.
 /// Instead the data must be uploaded separately and a raw pointer (`*const u64`) must be stored for
-pub struct GenerationParameters<S: SeedGettable + Sized> {
+pub struct GenerationParameters<S: SeedGettable + Sized, M: EdgeModel = Model> {
     pub seeds: S,
-    pub pregenerate_numbers: bool,
+    /// How the weights and positions of the nodes are obtained, see [RandomMode].
+    pub random_mode: RandomMode,
     pub gpu_blocks: u32,
+    /// Use geometric skip sampling in the CPU generator instead of testing every pair, see `generator_cpu::skip`.
+    pub skip_sampling: bool,
     pub dims: usize,
-    pub pareto: random::ParetoDistribution,
+    pub weights: random::WeightDistribution,
+    #[cfg_attr(
+        all(not(target_os = "cuda"), feature = "serde"),
+        serde(with = "serde_float")
+    )]
     pub alpha: f32,
+    /// Scaling constant c of the probability function, see [compute_probability](crate::algorithm::compute_probability).
+    pub c: f32,
     pub w: f32,
     pub v: u64,
     pub tile_size: u64,
     pub edgebuffer_size: u64,
     pub shard_index: usize,
     pub shard_count: usize,
+    pub graph_kind: GraphKind,
+    pub edge_randomness: EdgeRandomness,
+    /// The hash family all random numbers are derived from.
+    pub hash: HashFunction,
+    pub norm: Norm,
+    pub geometry: Geometry,
+    pub model: M,
 }
 
-impl<S: SeedGettable + Sized> GenerationParameters<S> {
+impl<S: SeedGettable + Sized, M: EdgeModel> GenerationParameters<S, M> {
     pub fn num_dimensions(&self) -> usize {
         self.dims
     }
@@ -86,12 +247,14 @@ impl<S: SeedGettable + Sized> GenerationParameters<S> {
         self.seeds.get_seed(s)
     }
 
+    /// Computes the weight of node j, see [EdgeModel::sample_weight].
     pub fn compute_weight(&self, j: u64) -> f32 {
-        random::uniform_to_pareto(self.compute_property(j, SeedEnum::Weight), &self.pareto)
+        self.model
+            .sample_weight(self.compute_property(j, SeedEnum::Weight), self)
     }
 
     pub fn compute_property(&self, j: u64, p: SeedEnum) -> f32 {
-        random::random_property(j, self.get_seed(p))
+        random::random_property(&self.hash, j, self.get_seed(p))
     }
 
     pub fn fill_dims(&self, j: u64, p: &mut [f32]) {
@@ -100,6 +263,16 @@ impl<S: SeedGettable + Sized> GenerationParameters<S> {
         }
     }
 
+    /// Whether the pair (i, j) has to be considered for an edge at all.
+    ///
+    /// For [GraphKind::Undirected] only pairs with i < j are candidates, which rules out self-loops.
+    pub fn is_candidate_pair(&self, i: u64, j: u64) -> bool {
+        match self.graph_kind {
+            GraphKind::Directed => true,
+            GraphKind::Undirected => i < j,
+        }
+    }
+
     pub fn pos_to_tile(&self, x: u64, y: u64) -> ((u64, u64), (u64, u64)) {
         let bx = x.div(self.tile_size) * self.tile_size;
         let by = y.div(self.tile_size) * self.tile_size;
@@ -108,3 +281,60 @@ impl<S: SeedGettable + Sized> GenerationParameters<S> {
         ((bx, by), (ex, ey))
     }
 }
+
+/// (De)serializes an [f32] that may be infinite, which formats like JSON cannot represent as a number.
+///
+/// Non-finite values are written as the strings `"inf"`, `"-inf"` and `"NaN"`.
+#[cfg(all(not(target_os = "cuda"), feature = "serde"))]
+pub mod serde_float {
+    use core::fmt;
+    use serde::de::{self, Visitor};
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(x: &f32, serializer: S) -> Result<S::Ok, S::Error> {
+        if x.is_finite() {
+            serializer.serialize_f32(*x)
+        } else if x.is_nan() {
+            serializer.serialize_str("NaN")
+        } else if *x > 0.0 {
+            serializer.serialize_str("inf")
+        } else {
+            serializer.serialize_str("-inf")
+        }
+    }
+
+    struct FloatVisitor;
+
+    impl<'de> Visitor<'de> for FloatVisitor {
+        type Value = f32;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a number or one of \"inf\", \"-inf\", \"NaN\"")
+        }
+
+        fn visit_f64<E: de::Error>(self, v: f64) -> Result<f32, E> {
+            Ok(v as f32)
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<f32, E> {
+            Ok(v as f32)
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<f32, E> {
+            Ok(v as f32)
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<f32, E> {
+            match v {
+                "inf" => Ok(f32::INFINITY),
+                "-inf" => Ok(f32::NEG_INFINITY),
+                "NaN" => Ok(f32::NAN),
+                _ => Err(E::invalid_value(de::Unexpected::Str(v), &self)),
+            }
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+        deserializer.deserialize_any(FloatVisitor)
+    }
+}
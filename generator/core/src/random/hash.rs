@@ -0,0 +1,186 @@
+//! The counter-based hash functions all randomness is derived from.
+//!
+//! Every random number is a hash of its coordinates (like the node index and a seed), which makes the numbers independent of the order they are computed in.
+//! The [HashFunction] enum selects one of the built-in hash families at run-time, [Murmur3] is the default.
+
+use crate::random::{murmur3, philox, splitmix64, xxh3};
+
+/// A family of counter-based hash functions.
+pub trait CounterHash: Copy + core::fmt::Debug {
+    /// 32-bit hash of two numbers.
+    fn hash32_2(&self, s1: u64, s2: u64) -> u32;
+    /// 32-bit hash of three numbers.
+    fn hash32_3(&self, s1: u64, s2: u64, s3: u64) -> u32;
+    /// 64-bit hash of three numbers.
+    fn hash64_3(&self, s1: u64, s2: u64, s3: u64) -> u64;
+}
+
+/// Murmur3, the 32-bit x86 variant and the first half of the 128-bit x64 variant.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Murmur3;
+
+impl CounterHash for Murmur3 {
+    fn hash32_2(&self, s1: u64, s2: u64) -> u32 {
+        murmur3::murmur3_32_2(s1, s2)
+    }
+
+    fn hash32_3(&self, s1: u64, s2: u64, s3: u64) -> u32 {
+        murmur3::murmur3_32_3(s1, s2, s3)
+    }
+
+    fn hash64_3(&self, s1: u64, s2: u64, s3: u64) -> u64 {
+        murmur3::murmur3_x64_128_3(s1, s2, s3).0
+    }
+}
+
+/// SplitMix64, the 32-bit hashes are the upper half of the 64-bit hashes.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SplitMix64;
+
+impl CounterHash for SplitMix64 {
+    fn hash32_2(&self, s1: u64, s2: u64) -> u32 {
+        (splitmix64::splitmix64_2(s1, s2) >> 32) as u32
+    }
+
+    fn hash32_3(&self, s1: u64, s2: u64, s3: u64) -> u32 {
+        (self.hash64_3(s1, s2, s3) >> 32) as u32
+    }
+
+    fn hash64_3(&self, s1: u64, s2: u64, s3: u64) -> u64 {
+        splitmix64::splitmix64_3(s1, s2, s3)
+    }
+}
+
+/// XXH3-64, the 32-bit hashes are the upper half of the 64-bit hashes.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Xxh3;
+
+impl CounterHash for Xxh3 {
+    fn hash32_2(&self, s1: u64, s2: u64) -> u32 {
+        (xxh3::xxh3_64_2(s1, s2) >> 32) as u32
+    }
+
+    fn hash32_3(&self, s1: u64, s2: u64, s3: u64) -> u32 {
+        (self.hash64_3(s1, s2, s3) >> 32) as u32
+    }
+
+    fn hash64_3(&self, s1: u64, s2: u64, s3: u64) -> u64 {
+        xxh3::xxh3_64_3(s1, s2, s3)
+    }
+}
+
+/// Philox4x32-10, the hashes are the first one or two words of the output.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Philox;
+
+impl CounterHash for Philox {
+    fn hash32_2(&self, s1: u64, s2: u64) -> u32 {
+        philox::philox_2(s1, s2)[0]
+    }
+
+    fn hash32_3(&self, s1: u64, s2: u64, s3: u64) -> u32 {
+        philox::philox_3(s1, s2, s3)[0]
+    }
+
+    fn hash64_3(&self, s1: u64, s2: u64, s3: u64) -> u64 {
+        let r = philox::philox_3(s1, s2, s3);
+        ((r[0] as u64) << 32) | (r[1] as u64)
+    }
+}
+
+/// The built-in hash families, selectable at run-time.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "gpu"),
+    derive(cust::DeviceCopy)
+)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum HashFunction {
+    /// See [Murmur3].
+    Murmur3,
+    /// See [SplitMix64].
+    SplitMix64,
+    /// See [Xxh3].
+    Xxh3,
+    /// See [Philox].
+    Philox,
+}
+
+impl Default for HashFunction {
+    fn default() -> Self {
+        Self::Murmur3
+    }
+}
+
+impl CounterHash for HashFunction {
+    fn hash32_2(&self, s1: u64, s2: u64) -> u32 {
+        match self {
+            HashFunction::Murmur3 => Murmur3.hash32_2(s1, s2),
+            HashFunction::SplitMix64 => SplitMix64.hash32_2(s1, s2),
+            HashFunction::Xxh3 => Xxh3.hash32_2(s1, s2),
+            HashFunction::Philox => Philox.hash32_2(s1, s2),
+        }
+    }
+
+    fn hash32_3(&self, s1: u64, s2: u64, s3: u64) -> u32 {
+        match self {
+            HashFunction::Murmur3 => Murmur3.hash32_3(s1, s2, s3),
+            HashFunction::SplitMix64 => SplitMix64.hash32_3(s1, s2, s3),
+            HashFunction::Xxh3 => Xxh3.hash32_3(s1, s2, s3),
+            HashFunction::Philox => Philox.hash32_3(s1, s2, s3),
+        }
+    }
+
+    fn hash64_3(&self, s1: u64, s2: u64, s3: u64) -> u64 {
+        match self {
+            HashFunction::Murmur3 => Murmur3.hash64_3(s1, s2, s3),
+            HashFunction::SplitMix64 => SplitMix64.hash64_3(s1, s2, s3),
+            HashFunction::Xxh3 => Xxh3.hash64_3(s1, s2, s3),
+            HashFunction::Philox => Philox.hash64_3(s1, s2, s3),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn murmur3_is_the_default() {
+        assert_eq!(HashFunction::default(), HashFunction::Murmur3);
+        assert_eq!(HashFunction::Murmur3.hash32_2(1, 2), 0x80e3_e6cb);
+        assert_eq!(HashFunction::Murmur3.hash32_3(1, 2, 3), 0xa440_7a2e);
+        assert_eq!(
+            HashFunction::Murmur3.hash64_3(1, 2, 3),
+            0xf586_f1c9_c8c3_84c5
+        );
+    }
+
+    #[test]
+    fn families_are_distinct() {
+        let all = [
+            HashFunction::Murmur3,
+            HashFunction::SplitMix64,
+            HashFunction::Xxh3,
+            HashFunction::Philox,
+        ];
+        for (k, a) in all.iter().enumerate() {
+            for b in all[k + 1..].iter() {
+                assert_ne!(a.hash64_3(1, 2, 3), b.hash64_3(1, 2, 3), "{:?} {:?}", a, b);
+                assert_ne!(a.hash32_2(1, 2), b.hash32_2(1, 2), "{:?} {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn known_answers() {
+        assert_eq!(SplitMix64.hash64_3(1, 2, 3), 0xd073_4750_fde3_62b3);
+        assert_eq!(Xxh3.hash64_3(1, 2, 3), 0x281d_ef79_3232_88c6);
+        assert_eq!(Xxh3.hash32_2(1, 2), 0xc484_6c74);
+        assert_eq!(Philox.hash32_3(0, 0, 0), 0x6627_e8d5);
+        assert_eq!(Philox.hash64_3(0, 0, 0), 0x6627_e8d5_e169_c58d);
+    }
+}
@@ -1,16 +1,27 @@
 //! Hash-based psuedo-random number generation
 
+pub mod hash;
 pub mod murmur3;
+pub mod philox;
+pub mod splitmix64;
+pub mod xxh3;
 
+use hash::CounterHash;
+
+use crate::model::HyperbolicParameters;
 #[cfg(target_os = "cuda")]
 use cuda_std::GpuFloat;
 use no_std_compat::prelude::v1::*;
 
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug, Copy, PartialEq)]
 #[cfg_attr(
     all(not(target_os = "cuda"), feature = "gpu"),
     derive(cust::DeviceCopy)
 )]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct ParetoDistribution {
     /// x_min
     pub x: f32,
@@ -28,16 +39,356 @@ pub fn uniform_to_pareto(u: f32, dist: &ParetoDistribution) -> f32 {
     dist.x / ((1.0f32 - u).powf(1.0f32 / dist.alpha))
 }
 
-pub fn random_property(i: u64, seed: u64) -> f32 {
-    let h = murmur3::murmur3_32_2(i, seed);
+/// The distribution the weights of the nodes are drawn from.
+#[derive(Clone, Debug, Copy, PartialEq)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "gpu"),
+    derive(cust::DeviceCopy)
+)]
+#[cfg_attr(
+    all(not(target_os = "cuda"), feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum WeightDistribution {
+    /// Pareto distribution, see [ParetoDistribution].
+    Pareto(ParetoDistribution),
+    /// Every node has the same weight. This gives random geometric graphs.
+    Constant(f32),
+    /// Log-normal distribution, the logarithm of the weights is normally distributed.
+    LogNormal {
+        /// Mean of the logarithm of the weights.
+        mu: f32,
+        /// Standard deviation of the logarithm of the weights.
+        sigma: f32,
+    },
+    /// Pareto distribution that is cut off at an upper bound.
+    BoundedPareto {
+        /// Lower bound of the weights.
+        x_min: f32,
+        /// Upper bound of the weights.
+        x_max: f32,
+        /// alpha value of the distribution
+        alpha: f32,
+    },
+    /// Integer weights following a power law, `P(X >= k)` is approximately `(k / x_min)^-alpha`.
+    ///
+    /// The probability mass function thus decays as `k^-(alpha + 1)`.
+    DiscretePowerLaw {
+        /// Smallest weight, must be a positive integer.
+        x_min: f32,
+        /// alpha value of the distribution
+        alpha: f32,
+    },
+}
+
+impl From<ParetoDistribution> for WeightDistribution {
+    fn from(dist: ParetoDistribution) -> Self {
+        Self::Pareto(dist)
+    }
+}
+
+impl WeightDistribution {
+    /// Maps a uniform number in `[0, 1)` to a weight using the inverse of the CDF of the distribution.
+    pub fn sample(&self, u: f32) -> f32 {
+        match *self {
+            Self::Pareto(dist) => uniform_to_pareto(u, &dist),
+            Self::Constant(w) => w,
+            Self::LogNormal { mu, sigma } => {
+                (mu as f64 + sigma as f64 * inverse_normal_cdf(u as f64)).exp() as f32
+            }
+            Self::BoundedPareto {
+                x_min,
+                x_max,
+                alpha,
+            } => {
+                let (x_min, x_max, alpha) = (x_min as f64, x_max as f64, alpha as f64);
+                let tail = 1.0f64 - (x_min / x_max).powf(alpha);
+                (x_min / (1.0f64 - (u as f64) * tail).powf(1.0f64 / alpha)) as f32
+            }
+            // Rounded continuous approximation, see Clauset, Shalizi & Newman, "Power-law distributions in empirical data", appendix D.
+            Self::DiscretePowerLaw { x_min, alpha } => {
+                ((x_min - 0.5f32) / (1.0f32 - u).powf(1.0f32 / alpha) + 0.5f32).floor()
+            }
+        }
+    }
+}
+
+/// Inverse of the CDF of the standard normal distribution.
+///
+/// Uses the rational approximation by Peter Acklam, which has a relative error below 1.15e-9.
+/// The input is clamped to the open interval (0, 1).
+#[allow(clippy::excessive_precision)]
+pub fn inverse_normal_cdf(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e+01,
+        2.209460984245205e+02,
+        -2.759285104469687e+02,
+        1.383577518672690e+02,
+        -3.066479806614716e+01,
+        2.506628277459239e+00,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e+01,
+        1.615858368580409e+02,
+        -1.556989798598866e+02,
+        6.680131188771972e+01,
+        -1.328068155288572e+01,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-03,
+        -3.223964580411365e-01,
+        -2.400758277161838e+00,
+        -2.549732539343734e+00,
+        4.374664141464968e+00,
+        2.938163982698783e+00,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-03,
+        3.224671290700398e-01,
+        2.445134137142996e+00,
+        3.754408661907416e+00,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    let p = p.max(f64::MIN_POSITIVE).min(1.0f64 - f64::EPSILON);
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0f64)
+    };
+
+    if p < P_LOW {
+        tail((-2.0f64 * p.ln()).sqrt())
+    } else if p > 1.0f64 - P_LOW {
+        -tail((-2.0f64 * (1.0f64 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5f64;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0f64)
+    }
+}
+
+/// Maps a uniform number to a radial coordinate of the hyperbolic random graph model.
+///
+/// The radii have density `alpha sinh(alpha r) / (cosh(alpha R) - 1)` on `[0, R]`, this is the inverse of its CDF.
+/// The computation is done in f64 as `cosh(alpha R)` quickly overflows an f32.
+pub fn uniform_to_hyperbolic_radius(u: f32, params: &HyperbolicParameters) -> f32 {
+    let alpha = params.alpha as f64;
+    let c = (alpha * params.radius as f64).cosh() - 1.0f64;
+    ((1.0f64 + (u as f64) * c).acosh() / alpha) as f32
+}
+
+pub fn random_property<H: CounterHash>(hash: &H, i: u64, seed: u64) -> f32 {
+    let h = hash.hash32_2(i, seed);
     let h = h as f64;
     let v = h / (u32::MAX as f64);
     v as f32
 }
 
-pub fn random_edge(i: u64, j: u64, seed: u64) -> f32 {
-    let h = murmur3::murmur3_32_3(i, j, seed);
+pub fn random_edge<H: CounterHash>(hash: &H, i: u64, j: u64, seed: u64) -> f32 {
+    let h = hash.hash32_3(i, j, seed);
     let h = h as f64;
     let v = h / (u32::MAX as f64);
     v as f32
 }
+
+/// Symmetric variant of [random_edge], the pairs (i, j) and (j, i) map to the same value.
+pub fn random_edge_symmetric<H: CounterHash>(hash: &H, i: u64, j: u64, seed: u64) -> f32 {
+    random_edge(hash, i.min(j), i.max(j), seed)
+}
+
+/// Maps a 64-bit hash to `[0, 1]`.
+///
+/// Small values keep their full resolution, the smallest positive value is `2^-64`.
+pub fn u64_to_unit(h: u64) -> f64 {
+    h as f64 * (1.0f64 / 18446744073709551616.0f64)
+}
+
+/// Variant of [random_edge] with 64 bits of randomness, see [EdgeRandomness::Bits64](crate::params::EdgeRandomness::Bits64).
+pub fn random_edge_64<H: CounterHash>(hash: &H, i: u64, j: u64, seed: u64) -> f64 {
+    u64_to_unit(hash.hash64_3(i, j, seed))
+}
+
+/// Symmetric variant of [random_edge_64], the pairs (i, j) and (j, i) map to the same value.
+pub fn random_edge_symmetric_64<H: CounterHash>(hash: &H, i: u64, j: u64, seed: u64) -> f64 {
+    random_edge_64(hash, i.min(j), i.max(j), seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand_xoshiro::rand_core::SeedableRng;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    /// CDF of the standard normal distribution, with the erf approximation 7.1.26 of Abramowitz and Stegun (absolute error below 1.5e-7).
+    fn normal_cdf(z: f64) -> f64 {
+        let x = z.abs() / core::f64::consts::SQRT_2;
+        let t = 1.0 / (1.0 + 0.3275911 * x);
+        let poly = t
+            * (0.254829592
+                + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+        let erf = 1.0 - poly * (-x * x).exp();
+        0.5 * (1.0 + erf.copysign(z))
+    }
+
+    /// Checks the empirical CDF of the distribution against the expected CDF at the given points.
+    fn check_cdf<F: Fn(f64) -> f64>(dist: WeightDistribution, points: &[f64], cdf: F) {
+        let mut rng = Xoshiro256StarStar::seed_from_u64(4);
+        let n = 100_000;
+        let mut below = [0usize; 8];
+        for _ in 0..n {
+            let sample = dist.sample(rng.gen()) as f64;
+            for (x, b) in points.iter().zip(below.iter_mut()) {
+                if sample <= *x {
+                    *b += 1;
+                }
+            }
+        }
+
+        for (x, b) in points.iter().zip(below.iter()) {
+            let empirical = *b as f64 / n as f64;
+            assert!(
+                (empirical - cdf(*x)).abs() < 0.01,
+                "{:?}: P(X <= {}) = {}, expected {}",
+                dist,
+                x,
+                empirical,
+                cdf(*x)
+            );
+        }
+    }
+
+    #[test]
+    fn inverse_normal_cdf_known_values() {
+        for (p, z) in [
+            (0.5, 0.0),
+            (0.975, 1.959963984540054),
+            (0.1, -1.2815515655446004),
+            (0.001, -3.090232306167813),
+            (1e-10, -6.361340902404056),
+        ] {
+            let actual = inverse_normal_cdf(p);
+            assert!(
+                (actual - z).abs() < 1e-8 * z.abs().max(1.0),
+                "{} != {}",
+                actual,
+                z
+            );
+        }
+    }
+
+    #[test]
+    fn pareto_distribution() {
+        let dist = ParetoDistribution::new(2.0, 1.5);
+        check_cdf(dist.into(), &[2.5, 4.0, 10.0], |x| {
+            1.0 - (2.0 / x).powf(1.5)
+        });
+    }
+
+    #[test]
+    fn constant_distribution() {
+        let dist = WeightDistribution::Constant(3.0);
+        let mut rng = Xoshiro256StarStar::seed_from_u64(5);
+        for _ in 0..1000 {
+            assert_eq!(dist.sample(rng.gen()), 3.0);
+        }
+    }
+
+    #[test]
+    fn log_normal_distribution() {
+        let (mu, sigma) = (1.0f64, 0.5f64);
+        let dist = WeightDistribution::LogNormal {
+            mu: mu as f32,
+            sigma: sigma as f32,
+        };
+        check_cdf(dist, &[0.8, 1.5, 2.0, 2.7, 3.5, 5.0, 8.0], |x| {
+            normal_cdf((x.ln() - mu) / sigma)
+        });
+    }
+
+    #[test]
+    fn bounded_pareto_distribution() {
+        let (l, h, a) = (1.0f64, 50.0f64, 1.2f64);
+        let dist = WeightDistribution::BoundedPareto {
+            x_min: l as f32,
+            x_max: h as f32,
+            alpha: a as f32,
+        };
+        check_cdf(dist, &[1.5, 5.0, 20.0, 49.9, 50.0], |x| {
+            (1.0 - (l / x).powf(a)) / (1.0 - (l / h).powf(a))
+        });
+
+        let mut rng = Xoshiro256StarStar::seed_from_u64(6);
+        for _ in 0..10000 {
+            let x = dist.sample(rng.gen());
+            assert!((1.0..=50.0).contains(&x), "{} out of bounds", x);
+        }
+    }
+
+    #[test]
+    fn discrete_power_law_distribution() {
+        let dist = WeightDistribution::DiscretePowerLaw {
+            x_min: 2.0,
+            alpha: 1.5,
+        };
+        // P(X <= k) = P(continuous sample < k + 0.5), see WeightDistribution::DiscretePowerLaw.
+        check_cdf(dist, &[2.0, 3.0, 5.0, 10.0], |k| {
+            1.0 - (1.5 / (k + 0.5)).powf(1.5)
+        });
+
+        let mut rng = Xoshiro256StarStar::seed_from_u64(7);
+        for _ in 0..10000 {
+            let x = dist.sample(rng.gen());
+            assert!(x >= 2.0 && x.fract() == 0.0, "{} is not an integer >= 2", x);
+        }
+    }
+
+    #[test]
+    fn tiny_edge_probabilities() {
+        // Fraction of the hashes that turn a pair with probability p into an edge, found by bisection.
+        fn accepted<F: Fn(u64) -> bool>(max: u64, is_edge: F) -> f64 {
+            let (mut lo, mut hi) = (0u64, max);
+            while lo < hi {
+                let mid = lo + (hi - lo) / 2;
+                if is_edge(mid) {
+                    lo = mid + 1;
+                } else {
+                    hi = mid;
+                }
+            }
+            lo as f64 / (max as f64 + 1.0)
+        }
+
+        for p in [1e-18f64, 1e-15, 1e-12, 1e-10, 1e-3, 0.5] {
+            let a = accepted(u64::MAX, |h| p > u64_to_unit(h));
+            // Off by at most a single hash, or the rounding of large hashes to f64.
+            let tolerance = (2.0 * u64_to_unit(1)).max(p * 1e-12);
+            assert!((a - p).abs() <= tolerance, "p = {}: {}", p, a);
+        }
+
+        // With 32 bits only the zero hash accepts a probability below 2^-32.
+        let p = 1e-12f32;
+        let a = accepted(u32::MAX as u64, |h| {
+            p > (h as f64 / (u32::MAX as f64)) as f32
+        });
+        assert_eq!(a, 1.0 / 4294967296.0);
+    }
+
+    #[test]
+    fn random_edge_64_is_uniform() {
+        let n = 1_000_000u64;
+        for t in [1e-3f64, 0.25] {
+            let below = (0..n)
+                .filter(|j| random_edge_64(&hash::Murmur3, 7, *j, 42) < t)
+                .count() as f64;
+            let (mean, sd) = (n as f64 * t, (n as f64 * t * (1.0 - t)).sqrt());
+            assert!((below - mean).abs() < 5.0 * sd, "{} below {}", below, t);
+        }
+        let h = hash::Murmur3;
+        assert_eq!(
+            random_edge_symmetric_64(&h, 3, 9, 1),
+            random_edge_symmetric_64(&h, 9, 3, 1)
+        );
+    }
+}
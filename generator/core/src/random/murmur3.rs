@@ -84,6 +84,62 @@ pub fn murmur3_32_2(s1: u64, s2: u64) -> u32 {
     finish(state, processed)
 }
 
+const C1_128: u64 = 0x87c3_7b91_1142_53d5;
+const C2_128: u64 = 0x4cf5_ad43_2745_937f;
+
+/// The x64 128-bit variant of murmur3 over the (big endian) bytes of the three numbers.
+pub fn murmur3_x64_128_3(s1: u64, s2: u64, s3: u64) -> (u64, u64) {
+    let (h1, h2) = process_16(0, 0, s1.swap_bytes(), s2.swap_bytes());
+    // The third number is the 8 byte tail.
+    let h1 = h1 ^ calc_k1_128(s3.swap_bytes());
+    finish_128(h1, h2, 24)
+}
+
+/// The x64 128-bit variant of murmur3 over the (big endian) bytes of the two numbers.
+pub fn murmur3_x64_128_2(s1: u64, s2: u64) -> (u64, u64) {
+    let (h1, h2) = process_16(0, 0, s1.swap_bytes(), s2.swap_bytes());
+    finish_128(h1, h2, 16)
+}
+
+fn process_16(h1: u64, h2: u64, k1: u64, k2: u64) -> (u64, u64) {
+    let h1 = (h1 ^ calc_k1_128(k1))
+        .rotate_left(27)
+        .wrapping_add(h2)
+        .wrapping_mul(5)
+        .wrapping_add(0x52dc_e729);
+    let h2 = (h2 ^ calc_k2_128(k2))
+        .rotate_left(31)
+        .wrapping_add(h1)
+        .wrapping_mul(5)
+        .wrapping_add(0x3849_5ab5);
+    (h1, h2)
+}
+
+fn calc_k1_128(k: u64) -> u64 {
+    k.wrapping_mul(C1_128).rotate_left(31).wrapping_mul(C2_128)
+}
+
+fn calc_k2_128(k: u64) -> u64 {
+    k.wrapping_mul(C2_128).rotate_left(33).wrapping_mul(C1_128)
+}
+
+fn finish_128(h1: u64, h2: u64, processed: u64) -> (u64, u64) {
+    let (mut h1, mut h2) = (h1 ^ processed, h2 ^ processed);
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+    h1 = fmix_64(h1);
+    h2 = fmix_64(h2);
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+    (h1, h2)
+}
+
+fn fmix_64(k: u64) -> u64 {
+    let k = (k ^ (k >> 33)).wrapping_mul(0xff51_afd7_ed55_8ccd);
+    let k = (k ^ (k >> 33)).wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    k ^ (k >> 33)
+}
+
 fn finish(state: u32, processed: u32) -> u32 {
     let mut hash = state;
     hash ^= processed as u32;
@@ -105,7 +161,7 @@ fn calc_k(k: u32) -> u32 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use mur3::murmurhash3_x86_32;
+    use mur3::{murmurhash3_x64_128, murmurhash3_x86_32};
     use rand::Rng;
     use rand_xoshiro::rand_core::SeedableRng;
     use rand_xoshiro::Xoshiro256StarStar;
@@ -141,4 +197,36 @@ mod tests {
             assert_eq!(actual, expected, "hashing [{}, {}]", s1, s2);
         }
     }
+
+    #[test]
+    fn test_equality_x64_128_3() {
+        let mut rng = Xoshiro256StarStar::seed_from_u64(1);
+
+        for _ in 0..10240 {
+            let s1: u64 = rng.gen();
+            let s2: u64 = rng.gen();
+            let s3: u64 = rng.gen();
+
+            let expected = murmurhash3_x64_128(
+                &[s1.to_be_bytes(), s2.to_be_bytes(), s3.to_be_bytes()].concat(),
+                0,
+            );
+            let actual = murmur3_x64_128_3(s1, s2, s3);
+            assert_eq!(actual, expected, "hashing [{}, {}, {}]", s1, s2, s3);
+        }
+    }
+
+    #[test]
+    fn test_equality_x64_128_2() {
+        let mut rng = Xoshiro256StarStar::seed_from_u64(1);
+
+        for _ in 0..10240 {
+            let s1: u64 = rng.gen();
+            let s2: u64 = rng.gen();
+
+            let expected = murmurhash3_x64_128(&[s1.to_be_bytes(), s2.to_be_bytes()].concat(), 0);
+            let actual = murmur3_x64_128_2(s1, s2);
+            assert_eq!(actual, expected, "hashing [{}, {}]", s1, s2);
+        }
+    }
 }
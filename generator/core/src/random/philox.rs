@@ -0,0 +1,81 @@
+//! The Philox4x32-10 counter-based generator, see "Parallel random numbers: as easy as 1, 2, 3" (Salmon et al., SC 2011).
+
+const M0: u32 = 0xd251_1f53;
+const M1: u32 = 0xcd9e_8d57;
+const W0: u32 = 0x9e37_79b9;
+const W1: u32 = 0xbb67_ae85;
+
+fn mulhilo(a: u32, b: u32) -> (u32, u32) {
+    let product = a as u64 * b as u64;
+    ((product >> 32) as u32, product as u32)
+}
+
+/// Encrypts the counter with the key using 10 rounds of Philox4x32.
+pub fn philox4x32_10(ctr: [u32; 4], key: [u32; 2]) -> [u32; 4] {
+    let (mut ctr, mut key) = (ctr, key);
+    for round in 0..10 {
+        if round > 0 {
+            key = [key[0].wrapping_add(W0), key[1].wrapping_add(W1)];
+        }
+        let (hi0, lo0) = mulhilo(M0, ctr[0]);
+        let (hi1, lo1) = mulhilo(M1, ctr[2]);
+        ctr = [hi1 ^ ctr[1] ^ key[0], lo1, hi0 ^ ctr[3] ^ key[1], lo0];
+    }
+    ctr
+}
+
+/// Splits a number in its low and high 32 bits.
+fn split(s: u64) -> (u32, u32) {
+    (s as u32, (s >> 32) as u32)
+}
+
+/// Hashes the numbers by using the first two as the counter and the last one as the key.
+pub fn philox_3(s1: u64, s2: u64, s3: u64) -> [u32; 4] {
+    let ((a, b), (c, d), (k0, k1)) = (split(s1), split(s2), split(s3));
+    philox4x32_10([a, b, c, d], [k0, k1])
+}
+
+/// Hashes the numbers by using the first one as the counter and the second one as the key.
+pub fn philox_2(s1: u64, s2: u64) -> [u32; 4] {
+    philox_3(s1, 0, s2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_answers() {
+        // From the kat_vectors of Random123.
+        assert_eq!(
+            philox4x32_10([0, 0, 0, 0], [0, 0]),
+            [0x6627e8d5, 0xe169c58d, 0xbc57ac4c, 0x9b00dbd8]
+        );
+        assert_eq!(
+            philox4x32_10([u32::MAX; 4], [u32::MAX; 2]),
+            [0x408f276d, 0x41c83b0e, 0xa20bc7c6, 0x6d5451fd]
+        );
+        assert_eq!(
+            philox4x32_10(
+                [0x243f6a88, 0x85a308d3, 0x13198a2e, 0x03707344],
+                [0xa4093822, 0x299f31d0]
+            ),
+            [0xd16cfe09, 0x94fdcceb, 0x5001e420, 0x24126ea1]
+        );
+    }
+
+    #[test]
+    fn test_layout() {
+        let s1 = 0x85a3_08d3_243f_6a88u64;
+        let s2 = 0x0370_7344_1319_8a2eu64;
+        let s3 = 0x299f_31d0_a409_3822u64;
+        assert_eq!(
+            philox_3(s1, s2, s3),
+            [0xd16cfe09, 0x94fdcceb, 0x5001e420, 0x24126ea1]
+        );
+        assert_eq!(
+            philox_2(0, 0),
+            [0x6627e8d5, 0xe169c58d, 0xbc57ac4c, 0x9b00dbd8]
+        );
+    }
+}
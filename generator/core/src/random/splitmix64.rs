@@ -0,0 +1,52 @@
+//! SplitMix64, see "Fast splittable pseudorandom number generators" (Steele, Lea and Flood, OOPSLA 2014).
+
+/// The increment of the SplitMix64 state, the golden ratio.
+const GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Output of a SplitMix64 generator with the given state, the state is advanced before mixing.
+///
+/// This is the first number a generator seeded with `state` produces.
+pub fn splitmix64(state: u64) -> u64 {
+    let z = state.wrapping_add(GAMMA);
+    let z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    let z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Hashes the two numbers by feeding them one after the other through [splitmix64].
+pub fn splitmix64_2(s1: u64, s2: u64) -> u64 {
+    splitmix64(splitmix64(s1) ^ s2)
+}
+
+/// Hashes the three numbers by feeding them one after the other through [splitmix64].
+pub fn splitmix64_3(s1: u64, s2: u64, s3: u64) -> u64 {
+    splitmix64(splitmix64_2(s1, s2) ^ s3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_xoshiro::rand_core::{RngCore, SeedableRng};
+    use rand_xoshiro::{SplitMix64, Xoshiro256StarStar};
+
+    #[test]
+    fn test_known_answers() {
+        assert_eq!(splitmix64(1), 0x910a_2dec_8902_5cc1);
+        assert_eq!(splitmix64(1u64.wrapping_add(GAMMA)), 0xbeeb_8da1_658e_ec67);
+    }
+
+    #[test]
+    fn test_equality() {
+        let mut rng = Xoshiro256StarStar::seed_from_u64(0);
+
+        for _ in 0..1024 {
+            let seed = rng.next_u64();
+            let mut expected = SplitMix64::seed_from_u64(seed);
+            let mut state = seed;
+            for _ in 0..10 {
+                assert_eq!(splitmix64(state), expected.next_u64(), "seed {}", seed);
+                state = state.wrapping_add(GAMMA);
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! The 64-bit XXH3 hash (with the default secret and seed 0), specialised to inputs of two or three numbers.
+//!
+//! See <https://github.com/Cyan4973/xxHash>.
+
+/// The first bytes of the default secret of XXH3, the short inputs here do not use the rest.
+const SECRET: [u8; 56] = [
+    0xb8, 0xfe, 0x6c, 0x39, 0x23, 0xa4, 0x4b, 0xbe, 0x7c, 0x01, 0x81, 0x2c, 0xf7, 0x21, 0xad, 0x1c,
+    0xde, 0xd4, 0x6d, 0xe9, 0x83, 0x90, 0x97, 0xdb, 0x72, 0x40, 0xa4, 0xa4, 0xb7, 0xb3, 0x67, 0x1f,
+    0xcb, 0x79, 0xe6, 0x4e, 0xcc, 0xc0, 0xe5, 0x78, 0x82, 0x5a, 0xd0, 0x7d, 0xcc, 0xff, 0x72, 0x21,
+    0xb8, 0x08, 0x46, 0x74, 0xf7, 0x43, 0x24, 0x8e,
+];
+
+const PRIME64_1: u64 = 0x9e37_79b1_85eb_ca87;
+
+/// The 64-bit little endian word of the secret at the byte offset.
+fn secret(offset: usize) -> u64 {
+    let mut buffer = [0u8; 8];
+    buffer.copy_from_slice(&SECRET[offset..offset + 8]);
+    u64::from_le_bytes(buffer)
+}
+
+fn mul128_fold64(a: u64, b: u64) -> u64 {
+    let product = a as u128 * b as u128;
+    (product as u64) ^ ((product >> 64) as u64)
+}
+
+fn avalanche(h: u64) -> u64 {
+    let h = (h ^ (h >> 37)).wrapping_mul(0x1656_6791_9e37_79f9);
+    h ^ (h >> 32)
+}
+
+/// Mixes 16 bytes of input, given as two little endian words, with the secret at the byte offset.
+fn mix16(lo: u64, hi: u64, offset: usize) -> u64 {
+    mul128_fold64(lo ^ secret(offset), hi ^ secret(offset + 8))
+}
+
+/// XXH3-64 over the (big endian) bytes of the two numbers.
+pub fn xxh3_64_2(s1: u64, s2: u64) -> u64 {
+    // The 9 to 16 bytes case.
+    let lo = s1.swap_bytes() ^ (secret(24) ^ secret(32));
+    let hi = s2.swap_bytes() ^ (secret(40) ^ secret(48));
+    avalanche(
+        16u64
+            .wrapping_add(lo.swap_bytes())
+            .wrapping_add(hi)
+            .wrapping_add(mul128_fold64(lo, hi)),
+    )
+}
+
+/// XXH3-64 over the (big endian) bytes of the three numbers.
+pub fn xxh3_64_3(s1: u64, s2: u64, s3: u64) -> u64 {
+    // The 17 to 32 bytes case, the first and the last 16 bytes are mixed.
+    let (k1, k2, k3) = (s1.swap_bytes(), s2.swap_bytes(), s3.swap_bytes());
+    avalanche(
+        24u64
+            .wrapping_mul(PRIME64_1)
+            .wrapping_add(mix16(k1, k2, 0))
+            .wrapping_add(mix16(k2, k3, 16)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand_xoshiro::rand_core::SeedableRng;
+    use rand_xoshiro::Xoshiro256StarStar;
+    use xxhash_rust::xxh3::xxh3_64;
+
+    #[test]
+    fn test_known_answers() {
+        assert_eq!(xxh3_64_2(1, 2), 0xc484_6c74_c4b3_20ad);
+        assert_eq!(xxh3_64_3(1, 2, 3), 0x281d_ef79_3232_88c6);
+    }
+
+    #[test]
+    fn test_equality_3() {
+        let mut rng = Xoshiro256StarStar::seed_from_u64(0);
+
+        for _ in 0..10240 {
+            let s1: u64 = rng.gen();
+            let s2: u64 = rng.gen();
+            let s3: u64 = rng.gen();
+
+            let expected =
+                xxh3_64(&[s1.to_be_bytes(), s2.to_be_bytes(), s3.to_be_bytes()].concat());
+            assert_eq!(
+                xxh3_64_3(s1, s2, s3),
+                expected,
+                "hashing [{}, {}, {}]",
+                s1,
+                s2,
+                s3
+            );
+        }
+    }
+
+    #[test]
+    fn test_equality_2() {
+        let mut rng = Xoshiro256StarStar::seed_from_u64(0);
+
+        for _ in 0..10240 {
+            let s1: u64 = rng.gen();
+            let s2: u64 = rng.gen();
+
+            let expected = xxh3_64(&[s1.to_be_bytes(), s2.to_be_bytes()].concat());
+            assert_eq!(xxh3_64_2(s1, s2), expected, "hashing [{}, {}]", s1, s2);
+        }
+    }
+}
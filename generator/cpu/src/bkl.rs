@@ -0,0 +1,568 @@
+//! Expected linear-time sampler by Bringmann, Keusch and Lengler.
+//!
+//! See "Geometric inhomogeneous random graphs" (Theoretical Computer Science, 2019) and "Efficiently generating geometric inhomogeneous and hyperbolic random graphs" (Bläsius et al., ESA 2019).
+//!
+//! Instead of evaluating every pair of nodes in a tile, the nodes of both sides of the tile are split into weight layers and sorted into nested grid cells.
+//! For every pair of layers there is a target level of the grid, at which cells are about as small as the distance below which nodes of those layers connect with a high probability.
+//! Pairs of touching cells on the target level are sampled pair by pair.
+//! Pairs of cells that are not touching, but whose parents are, are far apart.
+//! The [EdgeModel::probability_bound] gives an upper bound p̄ on the probability of all their pairs, so geometric jumps skip directly to the next pair that is a candidate with probability p̄.
+//! Such a candidate is then accepted with probability p / p̄.
+//!
+//! The edges follow the same distribution as those of the [CPUGenerator](crate::CPUGenerator), but they are drawn from a seeded RNG per tile instead of the per-pair hash.
+//! Their number per tile is proportional to the number of edges plus the number of nodes in the tile, so large tiles (up to the number of vertices) give the best performance.
+//! The positions of the nodes must lie in the unit cube.
+
+use crate::{sample_candidates, send_buffered, tile_rng, NodeSource, PregeneratedNodes};
+use crossbeam_channel::{Receiver, Sender};
+use generator_common::params::ext::GenerationParametersExt;
+use generator_common::params::{EdgeModel, GenerationParameters, Geometry, VecSeeds};
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::sync::Arc;
+use tracing::{info, instrument};
+
+/// Nodes with a weight of at least `2^MAX_LAYERS` times the smallest weight all end up in the last layer.
+const MAX_LAYERS: usize = 64;
+
+/// Generator that implements the sampler by Bringmann, Keusch and Lengler. See the [module documentation](self).
+///
+/// Only models that provide a [probability bound](EdgeModel::probability_bound) are supported.
+/// Like the [CPUGenerator](crate::CPUGenerator), the workers share the [PregeneratedNodes] they are constructed with.
+pub struct BKLGenerator {
+    /// The table shared by the workers, if any.
+    nodes: Option<Arc<PregeneratedNodes>>,
+}
+
+impl<M: EdgeModel> generator_common::generator::GraphGenerator<M> for BKLGenerator {
+    type ConstructArgument = Option<Arc<PregeneratedNodes>>;
+
+    fn new(nodes: Self::ConstructArgument) -> anyhow::Result<Self> {
+        Ok(Self { nodes })
+    }
+
+    #[instrument(skip_all)]
+    fn generate(
+        &self,
+        sender: Sender<Vec<(u64, u64)>>,
+        finisher: Sender<((u64, u64), (u64, u64))>,
+        receiver: Receiver<((u64, u64), (u64, u64))>,
+        params: &GenerationParameters<VecSeeds, M>,
+    ) -> anyhow::Result<()> {
+        params.validate()?;
+        if params
+            .model
+            .probability_bound(1.0, 1.0, 1.0, params)
+            .is_none()
+        {
+            anyhow::bail!(
+                "The BKL generator requires a model with a probability bound, {:?} has none.",
+                params.model
+            );
+        }
+
+        info!("Running!");
+        let nodes = NodeSource::with_pregenerated(params, self.nodes.clone())?;
+        for (start, end) in receiver {
+            info!("Job: {:?} -> {:?}", start, end);
+            let tile_nodes = nodes.for_tile(start, end);
+            let nodes = tile_nodes.as_ref().unwrap_or(&nodes);
+            send_buffered(&sender, params.edgebuffer_size, |cb| {
+                sample_tile(start, end, params, nodes, cb)
+            });
+            info!("Job done!");
+            finisher.send((start, end)).unwrap();
+        }
+        drop(sender);
+        drop(finisher);
+        info!("Thread exit.");
+        Ok(())
+    }
+}
+
+/// Samples the edges (i, j) of the tile, with i in `[start.0, end.0)` and j in `[start.1, end.1)`.
+pub fn sample_tile<M: EdgeModel, F: FnMut(u64, u64)>(
+    start: (u64, u64),
+    end: (u64, u64),
+    params: &GenerationParameters<VecSeeds, M>,
+    nodes: &NodeSource<M>,
+    cb: F,
+) {
+    let dims = params.num_dimensions();
+    let a_range = start.0..end.0.min(params.v);
+    let b_range = start.1..end.1.min(params.v);
+    let n =
+        (a_range.end.saturating_sub(a_range.start)).max(b_range.end.saturating_sub(b_range.start));
+    if n == 0 {
+        return;
+    }
+
+    // About one node per cell on the deepest level, limited by the bits of the cell codes.
+    let levels = ((64 - (n - 1).leading_zeros()) as usize + dims - 1) / dims;
+    let levels = levels.min(63 / dims) as u32;
+
+    let a = Side::new(a_range, nodes, dims, levels);
+    let b = Side::new(b_range, nodes, dims, levels);
+
+    let targets = a
+        .max_weights
+        .iter()
+        .map(|w_i| {
+            b.max_weights
+                .iter()
+                .map(|w_j| target_level(*w_i, *w_j, params, levels))
+                .collect()
+        })
+        .collect();
+
+    let mut sampler = Sampler {
+        params,
+        dims,
+        levels,
+        targets,
+        rng: tile_rng(start, params),
+        cb,
+    };
+    let a_ranges: Vec<(usize, usize)> = a.layers.iter().map(|l| (0, l.len())).collect();
+    let b_ranges: Vec<(usize, usize)> = b.layers.iter().map(|l| (0, l.len())).collect();
+    sampler.visit(&a, &b, 0, 0, &a_ranges, 0, &b_ranges);
+}
+
+/// The deepest level at which the cells are still larger than the distance below which nodes with these weights connect with a high probability.
+fn target_level<M: EdgeModel>(
+    w_i: f32,
+    w_j: f32,
+    params: &GenerationParameters<VecSeeds, M>,
+    levels: u32,
+) -> u32 {
+    // The connection distance r satisfies r^d = c w_i w_j / W, cells on level l have sides of 2^-l.
+    let l = ((params.w as f64) / (params.c as f64 * w_i as f64 * w_j as f64)).log2()
+        / params.num_dimensions() as f64;
+    if l.is_nan() || l <= 0.0 {
+        0
+    } else {
+        (l.floor() as u32).min(levels)
+    }
+}
+
+/// The nodes of one side of a tile, split in weight layers and sorted by cell.
+struct Side {
+    /// Node index of every node in this side.
+    nodes: Vec<u64>,
+    /// Weight of every node in this side.
+    weights: Vec<f32>,
+    /// Positions of the nodes in this side, `dims` values per node.
+    positions: Vec<f32>,
+    /// Per weight layer the cell codes (on the deepest level) and the local indices of its nodes, sorted by cell.
+    layers: Vec<Vec<(u64, usize)>>,
+    /// Per weight layer the largest weight.
+    max_weights: Vec<f32>,
+}
+
+impl Side {
+    fn new<M: EdgeModel>(
+        range: std::ops::Range<u64>,
+        source: &NodeSource<M>,
+        dims: usize,
+        levels: u32,
+    ) -> Self {
+        let nodes: Vec<u64> = range.collect();
+        let weights: Vec<f32> = nodes.iter().map(|j| source.weight(*j)).collect();
+        let mut positions = vec![0.0f32; nodes.len() * dims];
+        for (j, p) in nodes.iter().zip(positions.chunks_mut(dims.max(1))) {
+            source.fill_position(*j, p);
+        }
+
+        let w_min = weights
+            .iter()
+            .cloned()
+            .filter(|w| *w > 0.0)
+            .fold(f32::INFINITY, f32::min);
+        let layer_of = |w: f32| {
+            if w > w_min {
+                ((w / w_min).log2() as usize).min(MAX_LAYERS - 1)
+            } else {
+                0
+            }
+        };
+        let num_layers = weights.iter().map(|w| layer_of(*w) + 1).max().unwrap_or(0);
+
+        let mut layers = vec![Vec::new(); num_layers];
+        let mut max_weights = vec![0.0f32; num_layers];
+        for (local, w) in weights.iter().enumerate() {
+            let l = layer_of(*w);
+            let code = cell_code(&positions[local * dims..(local + 1) * dims], levels);
+            layers[l].push((code, local));
+            max_weights[l] = max_weights[l].max(*w);
+        }
+        for layer in layers.iter_mut() {
+            layer.sort_unstable();
+        }
+
+        Self {
+            nodes,
+            weights,
+            positions,
+            layers,
+            max_weights,
+        }
+    }
+
+    fn position(&self, local: usize, dims: usize) -> &[f32] {
+        &self.positions[local * dims..(local + 1) * dims]
+    }
+
+    /// Ranges of the children of the cell `code` on `level`, which has the given ranges per layer.
+    fn child_ranges(
+        &self,
+        ranges: &[(usize, usize)],
+        code: u64,
+        level: u32,
+        dims: usize,
+        levels: u32,
+    ) -> Vec<(u64, Vec<(usize, usize)>)> {
+        let shift = dims as u32 * (levels - level - 1);
+        (0..(1u64 << dims))
+            .map(|s| {
+                let child = (code << dims) | s;
+                let (lo, hi) = (child << shift, (child + 1) << shift);
+                let child_ranges = ranges
+                    .iter()
+                    .zip(self.layers.iter())
+                    .map(|((start, end), layer)| {
+                        let slice = &layer[*start..*end];
+                        (
+                            start + slice.partition_point(|(c, _)| *c < lo),
+                            start + slice.partition_point(|(c, _)| *c < hi),
+                        )
+                    })
+                    .collect();
+                (child, child_ranges)
+            })
+            .filter(|(_, r): &(u64, Vec<(usize, usize)>)| r.iter().any(|(s, e)| s < e))
+            .collect()
+    }
+}
+
+/// Code of the cell on the deepest level that contains the position.
+///
+/// The bits of the coordinates are interleaved, such that the code of a cell on level l is the code of the cell on the deepest level shifted right by `d (levels - l)` bits.
+fn cell_code(p: &[f32], levels: u32) -> u64 {
+    let cells = 1u64 << levels;
+    let coords: Vec<u64> = p
+        .iter()
+        .map(|x| ((x.max(0.0) as f64 * cells as f64) as u64).min(cells - 1))
+        .collect();
+
+    let mut code = 0u64;
+    for bit in (0..levels).rev() {
+        for c in coords.iter() {
+            code = (code << 1) | ((c >> bit) & 1);
+        }
+    }
+    code
+}
+
+/// Coordinate along dimension `k` of the cell `code` on `level`.
+fn cell_coordinate(code: u64, level: u32, dims: usize, k: usize) -> u64 {
+    let mut c = 0u64;
+    for bit in (0..level).rev() {
+        c = (c << 1) | ((code >> (bit as usize * dims + (dims - 1 - k))) & 1);
+    }
+    c
+}
+
+/// Walks the cell pairs and samples the edges of every pair of layers.
+struct Sampler<'a, M: EdgeModel, F: FnMut(u64, u64)> {
+    params: &'a GenerationParameters<VecSeeds, M>,
+    dims: usize,
+    levels: u32,
+    /// Target level per pair of layers, see [target_level].
+    targets: Vec<Vec<u32>>,
+    rng: StdRng,
+    cb: F,
+}
+
+impl<'a, M: EdgeModel, F: FnMut(u64, u64)> Sampler<'a, M, F> {
+    /// Whether the cells touch, and a lower bound on the distance between their nodes.
+    fn cell_distance(&self, level: u32, a: u64, b: u64) -> (bool, f32) {
+        let cells = 1u64 << level;
+        let side = 1.0f32 / cells as f32;
+        let norm = self.params.norm;
+        let mut touching = true;
+        let mut acc = 0.0f32;
+        for k in 0..self.dims {
+            let (x, y) = (
+                cell_coordinate(a, level, self.dims, k),
+                cell_coordinate(b, level, self.dims, k),
+            );
+            let mut diff = if x > y { x - y } else { y - x };
+            if self.params.geometry == Geometry::Torus {
+                diff = diff.min(cells - diff);
+            }
+            touching &= diff <= 1;
+            acc = norm.accumulate(acc, diff.saturating_sub(1) as f32 * side);
+        }
+        (touching, norm.finish(acc))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn visit(
+        &mut self,
+        a: &Side,
+        b: &Side,
+        level: u32,
+        a_code: u64,
+        a_ranges: &[(usize, usize)],
+        b_code: u64,
+        b_ranges: &[(usize, usize)],
+    ) {
+        let (touching, d_min) = self.cell_distance(level, a_code, b_code);
+
+        let mut recurse = false;
+        for (i, (a_start, a_end)) in a_ranges.iter().enumerate() {
+            if a_start == a_end {
+                continue;
+            }
+            for (j, (b_start, b_end)) in b_ranges.iter().enumerate() {
+                let target = self.targets[i][j];
+                // Pairs of layers with a target level above this one were sampled in a parent cell pair.
+                if b_start == b_end || target < level {
+                    continue;
+                }
+                if touching && target > level {
+                    recurse = true;
+                } else {
+                    self.sample(a, i, (*a_start, *a_end), b, j, (*b_start, *b_end), d_min);
+                }
+            }
+        }
+
+        if recurse {
+            let a_children = a.child_ranges(a_ranges, a_code, level, self.dims, self.levels);
+            let b_children = b.child_ranges(b_ranges, b_code, level, self.dims, self.levels);
+            for (a_child, a_child_ranges) in a_children.iter() {
+                for (b_child, b_child_ranges) in b_children.iter() {
+                    self.visit(
+                        a,
+                        b,
+                        level + 1,
+                        *a_child,
+                        a_child_ranges,
+                        *b_child,
+                        b_child_ranges,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Samples the pairs of layer i of cell a and layer j of cell b, whose nodes are at least `d_min` apart.
+    #[allow(clippy::too_many_arguments)]
+    fn sample(
+        &mut self,
+        a: &Side,
+        i: usize,
+        (a_start, a_end): (usize, usize),
+        b: &Side,
+        j: usize,
+        (b_start, b_end): (usize, usize),
+        d_min: f32,
+    ) {
+        let params = self.params;
+        let bound = params
+            .model
+            .probability_bound(a.max_weights[i], b.max_weights[j], d_min, params)
+            .unwrap_or(1.0f32)
+            .min(1.0f32);
+
+        let b_len = (b_end - b_start) as u64;
+        let pairs = (a_end - a_start) as u64 * b_len;
+        let dims = self.dims;
+        let cb = &mut self.cb;
+        sample_candidates(&mut self.rng, bound, pairs, |rng, k| {
+            let (_, x) = a.layers[i][a_start + (k / b_len) as usize];
+            let (_, y) = b.layers[j][b_start + (k % b_len) as usize];
+            let (u, v) = (a.nodes[x], b.nodes[y]);
+            if params.is_candidate_pair(u, v) {
+                let p = params.model.probability(
+                    a.weights[x],
+                    b.weights[y],
+                    a.position(x, dims),
+                    b.position(y, dims),
+                    params,
+                );
+                if rng.gen::<f64>() * (bound as f64) < p as f64 {
+                    cb(u, v);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generator_common::params::ext::GenerationParametersExt;
+    use generator_common::params::{GenerationParametersBuilder, GraphKind, Norm, RandomMode};
+    use generator_common::random::ParetoDistribution;
+
+    fn params(
+        v: u64,
+        alpha: f32,
+        tile_size: u64,
+        graph_kind: GraphKind,
+        norm: Norm,
+        geometry: Geometry,
+    ) -> GenerationParameters<VecSeeds> {
+        GenerationParametersBuilder::new()
+            .weights(ParetoDistribution::new(1.0, 1.8))
+            .alpha(alpha)
+            .vertices(v)
+            .seeds(&[11, 12, 13, 14])
+            .tile_size(tile_size)
+            .graph_kind(graph_kind)
+            .norm(norm)
+            .geometry(geometry)
+            .build()
+            .unwrap()
+    }
+
+    fn bkl_edges(params: &GenerationParameters<VecSeeds>) -> Vec<(u64, u64)> {
+        let nodes = NodeSource::new(params);
+        let mut edges = Vec::new();
+        for (start, end) in params.tiles() {
+            sample_tile(start, end, params, &nodes, |i, j| edges.push((i, j)));
+        }
+        edges.sort_unstable();
+        edges
+    }
+
+    fn brute_force_edges(params: &GenerationParameters<VecSeeds>) -> Vec<(u64, u64)> {
+        let nodes = NodeSource::new(params);
+        let mut edges = Vec::new();
+        for (start, end) in params.tiles() {
+            crate::worker_function(start, end, params, &nodes, |i, j| edges.push((i, j)));
+        }
+        edges.sort_unstable();
+        edges
+    }
+
+    fn degrees(params: &GenerationParameters<VecSeeds>, edges: &[(u64, u64)]) -> Vec<u64> {
+        let mut degrees = vec![0u64; params.v as usize];
+        for (i, j) in edges {
+            degrees[*i as usize] += 1;
+            if params.graph_kind == GraphKind::Undirected {
+                degrees[*j as usize] += 1;
+            }
+        }
+        degrees.sort_unstable();
+        degrees
+    }
+
+    /// Largest difference between the empirical CDFs of two sorted samples of equal size.
+    fn ks_statistic(a: &[u64], b: &[u64]) -> f64 {
+        let (mut i, mut j, mut d) = (0usize, 0usize, 0usize);
+        while i < a.len() && j < b.len() {
+            let x = a[i].min(b[j]);
+            while i < a.len() && a[i] == x {
+                i += 1;
+            }
+            while j < b.len() && b[j] == x {
+                j += 1;
+            }
+            d = d.max(if i > j { i - j } else { j - i });
+        }
+        d as f64 / a.len() as f64
+    }
+
+    #[test]
+    fn cell_codes_nest() {
+        let p = [0.3f32, 0.8f32];
+        let code = cell_code(&p, 5);
+        for level in 0..=5u32 {
+            let prefix = code >> (2 * (5 - level));
+            let cells = (1u64 << level) as f32;
+            assert_eq!(cell_coordinate(prefix, level, 2, 0), (0.3 * cells) as u64);
+            assert_eq!(cell_coordinate(prefix, level, 2, 1), (0.8 * cells) as u64);
+        }
+    }
+
+    #[test]
+    fn threshold_matches_brute_force() {
+        // The threshold model is deterministic given the positions, so both samplers must agree exactly.
+        for graph_kind in [GraphKind::Directed, GraphKind::Undirected] {
+            for (norm, geometry) in [
+                (Norm::Max, Geometry::Torus),
+                (Norm::L2, Geometry::Torus),
+                (Norm::L1, Geometry::Cube),
+            ] {
+                for tile_size in [700, 256] {
+                    let params = params(700, f32::INFINITY, tile_size, graph_kind, norm, geometry);
+                    let expected = brute_force_edges(&params);
+                    assert!(!expected.is_empty(), "expected some edges");
+                    assert_eq!(
+                        bkl_edges(&params),
+                        expected,
+                        "{:?} {:?} {:?} tile size {}",
+                        graph_kind,
+                        norm,
+                        geometry,
+                        tile_size
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn random_modes_give_the_same_edges() {
+        let edges = |random_mode: RandomMode| {
+            let params = GenerationParametersBuilder::new()
+                .vertices(600)
+                .seeds(&[11, 12, 13, 14])
+                .tile_size(200)
+                .random_mode(random_mode)
+                .build()
+                .unwrap();
+            let mut edges = generator_common::generate_edges::<BKLGenerator, _>(
+                &params,
+                PregeneratedNodes::shared(&params),
+                2,
+            )
+            .collect::<anyhow::Result<Vec<_>>>()
+            .unwrap();
+            edges.sort_unstable();
+            edges
+        };
+        let pregenerated = edges(RandomMode::PreGenerate);
+        assert!(!pregenerated.is_empty());
+        assert_eq!(edges(RandomMode::TileCached), pregenerated);
+        assert_eq!(edges(RandomMode::OnDemand), pregenerated);
+    }
+
+    #[test]
+    fn degree_distribution_matches_brute_force() {
+        for graph_kind in [GraphKind::Directed, GraphKind::Undirected] {
+            let params = params(1500, 1.5, 1500, graph_kind, Norm::Max, Geometry::Torus);
+            let expected = brute_force_edges(&params);
+            let actual = bkl_edges(&params);
+
+            let ratio = actual.len() as f64 / expected.len() as f64;
+            assert!(
+                (ratio - 1.0).abs() < 0.05,
+                "{:?}: {} edges, expected about {}",
+                graph_kind,
+                actual.len(),
+                expected.len()
+            );
+
+            let d = ks_statistic(&degrees(&params, &actual), &degrees(&params, &expected));
+            assert!(d < 0.06, "{:?}: KS statistic {}", graph_kind, d);
+
+            if graph_kind == GraphKind::Undirected {
+                assert!(actual.iter().all(|(i, j)| i < j));
+            }
+        }
+    }
+}
@@ -0,0 +1,417 @@
+//! Spatial grid sampler for threshold GIRGs, the [Girg](generator_common::model::Girg) model with an infinite alpha.
+//!
+//! In the threshold model nodes i and j are connected iff their distance is at most `r = (c w_i w_j / W)^(1/d)`, see [compute_probability](generator_common::algorithm::compute_probability).
+//! Instead of testing every pair of a tile, the j-nodes of the tile are split into weight classes (a factor of two apart) and every class is put in its own uniform grid on the torus.
+//! For a node i and a class, the largest weight of the class gives the largest radius at which i can connect to any of its nodes.
+//! Every norm is at least the maximum norm, so only the cells overlapping the cube of that radius around i hold candidates.
+//! The candidates are decided with [generate_edge], so the edges are exactly those of [worker_function](crate::worker_function), in the same order.
+//!
+//! The work per tile is proportional to the number of candidates plus the number of nodes times the number of classes, so large tiles (up to the number of vertices) give the best performance.
+//! The positions of the nodes must lie in the unit cube.
+
+use crate::{send_buffered, NodeSource, PregeneratedNodes};
+use crossbeam_channel::{Receiver, Sender};
+use generator_common::algorithm::generate_edge;
+use generator_common::params::ext::GenerationParametersExt;
+use generator_common::params::{EdgeModel, GenerationParameters, Geometry, VecSeeds};
+use std::sync::Arc;
+use tracing::{info, instrument};
+
+/// Average number of nodes per grid cell.
+const NODES_PER_CELL: f64 = 16.0;
+
+/// The connection radius is enlarged by this fraction, such that rounding in the distance and the radius can never exclude a pair that is an edge.
+const RADIUS_MARGIN: f32 = 1e-4;
+
+/// Nodes with a weight of at least `2^MAX_CLASSES` times the smallest weight all end up in the last class.
+const MAX_CLASSES: usize = 64;
+
+/// Generator for threshold GIRGs that only tests the pairs of nearby grid cells. See the [module documentation](self).
+///
+/// Only the [Girg](generator_common::model::Girg) model with an infinite [alpha](GenerationParameters::alpha) is supported.
+/// Like the [CPUGenerator](crate::CPUGenerator), the workers share the [PregeneratedNodes] they are constructed with.
+pub struct GridGenerator {
+    /// The table shared by the workers, if any.
+    nodes: Option<Arc<PregeneratedNodes>>,
+}
+
+impl<M: EdgeModel> generator_common::generator::GraphGenerator<M> for GridGenerator {
+    type ConstructArgument = Option<Arc<PregeneratedNodes>>;
+
+    fn new(nodes: Self::ConstructArgument) -> anyhow::Result<Self> {
+        Ok(Self { nodes })
+    }
+
+    #[instrument(skip_all)]
+    fn generate(
+        &self,
+        sender: Sender<Vec<(u64, u64)>>,
+        finisher: Sender<((u64, u64), (u64, u64))>,
+        receiver: Receiver<((u64, u64), (u64, u64))>,
+        params: &GenerationParameters<VecSeeds, M>,
+    ) -> anyhow::Result<()> {
+        params.validate()?;
+        check_parameters(params)?;
+
+        info!("Running!");
+        let nodes = NodeSource::with_pregenerated(params, self.nodes.clone())?;
+        for (start, end) in receiver {
+            info!("Job: {:?} -> {:?}", start, end);
+            let tile_nodes = nodes.for_tile(start, end);
+            let nodes = tile_nodes.as_ref().unwrap_or(&nodes);
+            send_buffered(&sender, params.edgebuffer_size, |cb| {
+                sample_tile(start, end, params, nodes, cb)
+            });
+            info!("Job done!");
+            finisher.send((start, end)).unwrap();
+        }
+        drop(sender);
+        drop(finisher);
+        info!("Thread exit.");
+        Ok(())
+    }
+}
+
+/// Fails unless the parameters describe a threshold GIRG, the only graphs the [GridGenerator] supports.
+pub fn check_parameters<M: EdgeModel>(
+    params: &GenerationParameters<VecSeeds, M>,
+) -> anyhow::Result<()> {
+    if !params.model.is_girg() || !params.alpha.is_infinite() {
+        anyhow::bail!(
+            "The grid generator requires the threshold model (the GIRG model with an infinite alpha), got {:?} with alpha {}.",
+            params.model,
+            params.alpha
+        );
+    }
+    Ok(())
+}
+
+/// The j-nodes of one weight class, sorted into a uniform grid.
+struct Class {
+    /// Number of cells along every dimension.
+    cells: u64,
+    /// Largest weight in the class.
+    max_weight: f32,
+    /// Start of the nodes of every cell in `nodes`, followed by the total number of nodes.
+    cell_starts: Vec<usize>,
+    /// The nodes, ordered by cell.
+    nodes: Vec<u64>,
+    /// The weights of the nodes.
+    weights: Vec<f32>,
+    /// The positions of the nodes, `dims` values per node.
+    positions: Vec<f32>,
+}
+
+/// A run of cells along one dimension, see [Class::cell_range].
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+struct CellRange {
+    /// The first cell.
+    first: u64,
+    /// Number of cells, the cells after the first wrap around on the torus.
+    len: u64,
+}
+
+/// Index of the cell of coordinate `x` along one dimension.
+fn cell_coordinate(x: f32, cells: u64) -> u64 {
+    ((x as f64 * cells as f64) as u64).min(cells - 1)
+}
+
+impl Class {
+    /// Sorts the given nodes with their weights and positions into a grid.
+    fn new(members: &[(u64, f32, Vec<f32>)], dims: usize) -> Self {
+        let cells = ((members.len() as f64 / NODES_PER_CELL).powf(1.0 / dims as f64) as u64).max(1);
+        let cell_of = |p: &[f32]| {
+            p.iter()
+                .fold(0u64, |code, x| code * cells + cell_coordinate(*x, cells))
+                as usize
+        };
+
+        let mut cell_starts = vec![0usize; cells.pow(dims as u32) as usize + 1];
+        for (_, _, p) in members {
+            cell_starts[cell_of(p) + 1] += 1;
+        }
+        for c in 1..cell_starts.len() {
+            cell_starts[c] += cell_starts[c - 1];
+        }
+
+        let mut next = cell_starts.clone();
+        let mut nodes = vec![0u64; members.len()];
+        let mut weights = vec![0.0f32; members.len()];
+        let mut positions = vec![0.0f32; members.len() * dims];
+        for (j, w, p) in members {
+            let k = &mut next[cell_of(p)];
+            nodes[*k] = *j;
+            weights[*k] = *w;
+            positions[*k * dims..(*k + 1) * dims].copy_from_slice(p);
+            *k += 1;
+        }
+
+        Self {
+            cells,
+            max_weight: weights.iter().cloned().fold(0.0f32, f32::max),
+            cell_starts,
+            nodes,
+            weights,
+            positions,
+        }
+    }
+
+    /// The cells along one dimension that hold the coordinates within `radius` of `x`, each once.
+    fn cell_range(&self, x: f32, radius: f32, geometry: Geometry) -> CellRange {
+        let cells = self.cells as i64;
+        let lo = ((x - radius) as f64 * cells as f64).floor() as i64;
+        let hi = ((x + radius) as f64 * cells as f64).floor() as i64;
+        let (first, last) = match geometry {
+            Geometry::Torus => (
+                lo.rem_euclid(cells),
+                lo.rem_euclid(cells) + (hi - lo).min(cells - 1),
+            ),
+            Geometry::Cube => (lo.max(0), hi.min(cells - 1)),
+        };
+        CellRange {
+            first: first as u64,
+            len: (last - first + 1).max(0) as u64,
+        }
+    }
+
+    /// The `k`-th cell of the range.
+    #[inline]
+    fn cell(&self, range: CellRange, k: u64) -> u64 {
+        (range.first + k) % self.cells
+    }
+}
+
+/// Emits the edges (i, j) of the tile, with i in `[start.0, end.0)` and j in `[start.1, end.1)`.
+///
+/// The edges are those of [worker_function](crate::worker_function) in the same order, ordered by j and then by i.
+pub fn sample_tile<M: EdgeModel, F: FnMut(u64, u64)>(
+    start: (u64, u64),
+    end: (u64, u64),
+    params: &GenerationParameters<VecSeeds, M>,
+    nodes: &NodeSource<M>,
+    mut cb: F,
+) {
+    let dims = params.num_dimensions();
+    let rows = start.0..end.0.min(params.v);
+    let columns = start.1..end.1.min(params.v);
+    if rows.is_empty() || columns.is_empty() {
+        return;
+    }
+
+    let members: Vec<(u64, f32, Vec<f32>)> = columns
+        .map(|j| {
+            let mut p = vec![0.0f32; dims];
+            nodes.fill_position(j, &mut p);
+            (j, nodes.weight(j), p)
+        })
+        .collect();
+    let min_weight = members
+        .iter()
+        .map(|(_, w, _)| *w)
+        .fold(f32::INFINITY, f32::min);
+    let mut classes: Vec<Vec<(u64, f32, Vec<f32>)>> = vec![Vec::new(); MAX_CLASSES];
+    for member in members {
+        // The class only affects the performance, every class bounds the radius by its own largest weight.
+        let class = ((member.1 / min_weight).log2() as usize).min(MAX_CLASSES - 1);
+        classes[class].push(member);
+    }
+    let classes: Vec<Class> = classes
+        .iter()
+        .filter(|members| !members.is_empty())
+        .map(|members| Class::new(members, dims))
+        .collect();
+
+    let mut edges = Vec::new();
+    let mut p_i_prime = vec![0.0f32; dims];
+    let mut cell = vec![0u64; dims];
+    let mut ranges = vec![CellRange::default(); dims];
+    for i in rows {
+        let w_i = nodes.weight(i);
+        let p_i = nodes.position(i, &mut p_i_prime);
+        for class in &classes {
+            let radius = (params.c * (w_i * class.max_weight) / params.w)
+                .powf(1.0f32 / dims as f32)
+                * (1.0 + RADIUS_MARGIN)
+                + f32::EPSILON;
+            for (range, x) in ranges.iter_mut().zip(p_i) {
+                *range = class.cell_range(*x, radius, params.geometry);
+            }
+            if ranges.iter().any(|r| r.len == 0) {
+                continue;
+            }
+
+            // Visit every combination of the per-dimension cells.
+            cell.iter_mut().for_each(|c| *c = 0);
+            loop {
+                let code = cell
+                    .iter()
+                    .zip(ranges.iter())
+                    .fold(0u64, |code, (c, r)| code * class.cells + class.cell(*r, *c))
+                    as usize;
+                for k in class.cell_starts[code]..class.cell_starts[code + 1] {
+                    let j = class.nodes[k];
+                    let p_j = &class.positions[k * dims..(k + 1) * dims];
+                    if generate_edge(i, j, w_i, class.weights[k], p_i, p_j, params) {
+                        edges.push((j, i));
+                    }
+                }
+
+                let mut d = 0;
+                while d < dims {
+                    cell[d] += 1;
+                    if cell[d] < ranges[d].len {
+                        break;
+                    }
+                    cell[d] = 0;
+                    d += 1;
+                }
+                if d == dims {
+                    break;
+                }
+            }
+        }
+    }
+
+    edges.sort_unstable();
+    for (j, i) in edges {
+        cb(i, j);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generator_common::params::ext::GenerationParametersExt;
+    use generator_common::params::{GenerationParametersBuilder, GraphKind, Norm};
+    use generator_common::random::ParetoDistribution;
+
+    fn params(
+        dims: usize,
+        v: u64,
+        tile_size: u64,
+        graph_kind: GraphKind,
+        norm: Norm,
+        geometry: Geometry,
+    ) -> GenerationParameters<VecSeeds> {
+        let seeds: Vec<u64> = (0..dims as u64 + 2).map(|s| 31 + s).collect();
+        GenerationParametersBuilder::new()
+            .dimensions(dims)
+            .weights(ParetoDistribution::new(1.0, 2.5))
+            .alpha(f32::INFINITY)
+            .vertices(v)
+            .seeds(&seeds)
+            .tile_size(tile_size)
+            .graph_kind(graph_kind)
+            .norm(norm)
+            .geometry(geometry)
+            .build()
+            .unwrap()
+    }
+
+    fn grid_edges(params: &GenerationParameters<VecSeeds>) -> Vec<(u64, u64)> {
+        let nodes = NodeSource::new(params);
+        let mut edges = Vec::new();
+        for (start, end) in params.tiles() {
+            sample_tile(start, end, params, &nodes, |i, j| edges.push((i, j)));
+        }
+        edges
+    }
+
+    fn brute_force_edges(params: &GenerationParameters<VecSeeds>) -> Vec<(u64, u64)> {
+        let nodes = NodeSource::new(params);
+        let mut edges = Vec::new();
+        for (start, end) in params.tiles() {
+            crate::worker_function(start, end, params, &nodes, |i, j| edges.push((i, j)));
+        }
+        edges
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        for graph_kind in [GraphKind::Directed, GraphKind::Undirected] {
+            for (dims, norm, geometry) in [
+                (2, Norm::Max, Geometry::Torus),
+                (2, Norm::L2, Geometry::Torus),
+                (1, Norm::L1, Geometry::Torus),
+                (3, Norm::Lp(3.0), Geometry::Torus),
+                (2, Norm::L1, Geometry::Cube),
+            ] {
+                for tile_size in [600, 128] {
+                    let params = params(dims, 600, tile_size, graph_kind, norm, geometry);
+                    let expected = brute_force_edges(&params);
+                    assert!(!expected.is_empty(), "expected some edges");
+                    // The tiles are emitted in the same order, and the edges of every tile are ordered like those of the brute force.
+                    let actual = grid_edges(&params);
+                    assert_eq!(
+                        actual, expected,
+                        "{:?} {} {:?} {:?} tile size {}",
+                        graph_kind, dims, norm, geometry, tile_size
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generator_matches_cpu_generator() {
+        let params = params(
+            2,
+            1000,
+            300,
+            GraphKind::Undirected,
+            Norm::L2,
+            Geometry::Torus,
+        );
+        let run = |grid: bool| {
+            let nodes = PregeneratedNodes::shared(&params);
+            let mut edges = if grid {
+                generator_common::generate_edges::<GridGenerator, _>(&params, nodes, 2)
+            } else {
+                generator_common::generate_edges::<crate::CPUGenerator, _>(&params, nodes, 2)
+            }
+            .collect::<anyhow::Result<Vec<_>>>()
+            .unwrap();
+            edges.sort_unstable();
+            edges
+        };
+        assert_eq!(run(true), run(false));
+    }
+
+    #[test]
+    fn cell_ranges_wrap_on_the_torus() {
+        let class = Class {
+            cells: 10,
+            max_weight: 1.0,
+            cell_starts: Vec::new(),
+            nodes: Vec::new(),
+            weights: Vec::new(),
+            positions: Vec::new(),
+        };
+        let cells = |x: f32, radius: f32, geometry: Geometry| {
+            let range = class.cell_range(x, radius, geometry);
+            (0..range.len)
+                .map(|k| class.cell(range, k))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(cells(0.05, 0.1, Geometry::Torus), vec![9, 0, 1]);
+        assert_eq!(cells(0.95, 0.1, Geometry::Torus), vec![8, 9, 0]);
+        assert_eq!(cells(0.5, 0.8, Geometry::Torus).len(), 10);
+        assert_eq!(cells(0.05, 0.1, Geometry::Cube), vec![0, 1]);
+        assert_eq!(cells(0.95, 0.1, Geometry::Cube), vec![8, 9]);
+        assert_eq!(cells(0.5, 0.8, Geometry::Cube).len(), 10);
+    }
+
+    #[test]
+    fn rejects_the_soft_model() {
+        let mut params = params(2, 100, 100, GraphKind::Directed, Norm::Max, Geometry::Torus);
+        params.alpha = 1.5;
+        let results = generator_common::generate_edges::<GridGenerator, _>(&params, None, 1)
+            .collect::<Vec<_>>();
+        assert_eq!(results.len(), 1);
+        assert!(results[0]
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .contains("threshold"));
+    }
+}
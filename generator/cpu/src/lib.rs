@@ -1,64 +1,53 @@
 #![warn(missing_docs)]
 #![warn(clippy::missing_docs_in_private_items)]
+#![feature(portable_simd)]
 
 use crossbeam_channel::{Receiver, Sender};
 use generator_common::algorithm::generate_edge;
 use generator_common::params::ext::GenerationParametersExt;
-use generator_common::params::{GenerationParameters, VecSeeds};
+use generator_common::params::{
+    CounterHash, EdgeModel, GenerationParameters, RandomMode, SeedEnum, VecSeeds,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::ops::Range;
+use std::sync::Arc;
 use tracing::{debug, info, instrument, warn};
 
+pub mod bkl;
+pub mod grid;
+pub mod simd;
+pub mod skip;
+pub mod stealing;
+
+/// Calls `cb` for every edge (i, j) of the tile, testing every pair with the per-pair hash.
 #[inline]
-pub fn worker_function<F: FnMut(u64, u64)>(
+pub fn worker_function<M: EdgeModel, F: FnMut(u64, u64)>(
     start: (u64, u64),
     end: (u64, u64),
-    params: &GenerationParameters<VecSeeds>,
+    params: &GenerationParameters<VecSeeds, M>,
+    nodes: &NodeSource<M>,
     mut cb: F,
 ) {
     let mut i = start.0;
     let mut j = start.1;
 
-    // Pre-calculate the params for j.
-    let ws: Option<Vec<f32>> = if params.pregenerate_numbers {
-        Some(params.compute_weights())
-    } else {
-        None
-    };
-    let ps: Option<Vec<Vec<f32>>> = if params.pregenerate_numbers {
-        Some(params.compute_positions())
-    } else {
-        None
-    };
     let mut p_i_prime = Vec::new();
     let mut p_j_prime = Vec::new();
     p_i_prime.resize(params.num_dimensions(), 0.0f32);
     p_j_prime.resize(params.num_dimensions(), 0.0f32);
 
     loop {
-        let w_i = ws
-            .as_ref()
-            .map(|w| *w.get(i as usize).unwrap())
-            .unwrap_or_else(|| params.compute_weight(i));
-        let w_j = ws
-            .as_ref()
-            .map(|w| *w.get(j as usize).unwrap())
-            .unwrap_or_else(|| params.compute_weight(j));
-        let p_i = ps
-            .as_ref()
-            .map(|p| p.get(i as usize).unwrap())
-            .unwrap_or_else(|| {
-                params.fill_dims(i, &mut p_i_prime);
-                &p_i_prime
-            });
-        let p_j = ps
-            .as_ref()
-            .map(|p| p.get(j as usize).unwrap())
-            .unwrap_or_else(|| {
-                params.fill_dims(j, &mut p_j_prime);
-                &p_j_prime
-            });
+        // Pairs that can never be an edge (i >= j for undirected graphs) are skipped early.
+        if params.is_candidate_pair(i, j) {
+            let w_i = nodes.weight(i);
+            let w_j = nodes.weight(j);
+            let p_i = nodes.position(i, &mut p_i_prime);
+            let p_j = nodes.position(j, &mut p_j_prime);
 
-        if generate_edge(i, j, w_i, w_j, p_i, p_j, params) {
-            cb(i, j)
+            if generate_edge(i, j, w_i, w_j, p_i, p_j, params) {
+                cb(i, j)
+            }
         }
 
         // Increment i,j
@@ -78,13 +67,21 @@ pub fn worker_function<F: FnMut(u64, u64)>(
     }
 }
 
-pub struct CPUGenerator {}
+/// The CPU generator works with any [EdgeModel].
+///
+/// With [RandomMode::PreGenerate] the workers share the [PregeneratedNodes] they are constructed with, see [PregeneratedNodes::shared].
+/// With [RandomMode::TileCached] the nodes of every tile are computed once per tile instead, see [NodeSource::for_tile].
+/// With [RandomMode::OnDemand] the weights and positions are computed for every pair.
+pub struct CPUGenerator {
+    /// The table shared by the workers, if any.
+    nodes: Option<Arc<PregeneratedNodes>>,
+}
 
-impl generator_common::generator::GraphGenerator for CPUGenerator {
-    type ConstructArgument = ();
+impl<M: EdgeModel> generator_common::generator::GraphGenerator<M> for CPUGenerator {
+    type ConstructArgument = Option<Arc<PregeneratedNodes>>;
 
-    fn new(_: Self::ConstructArgument) -> anyhow::Result<Self> {
-        Ok(Self {})
+    fn new(nodes: Self::ConstructArgument) -> anyhow::Result<Self> {
+        Ok(Self { nodes })
     }
 
     #[instrument(skip_all)]
@@ -93,11 +90,13 @@ impl generator_common::generator::GraphGenerator for CPUGenerator {
         sender: Sender<Vec<(u64, u64)>>,
         finisher: Sender<((u64, u64), (u64, u64))>,
         receiver: Receiver<((u64, u64), (u64, u64))>,
-        params: &GenerationParameters<VecSeeds>,
+        params: &GenerationParameters<VecSeeds, M>,
     ) -> anyhow::Result<()> {
+        params.validate()?;
         info!("Running!");
+        let nodes = NodeSource::with_pregenerated(params, self.nodes.clone())?;
         for (start, end) in receiver {
-            worker(sender.clone(), start, end, params);
+            worker(sender.clone(), start, end, params, &nodes);
             finisher.send((start, end)).unwrap();
         }
         drop(sender);
@@ -107,19 +106,41 @@ impl generator_common::generator::GraphGenerator for CPUGenerator {
     }
 }
 
-pub fn worker(
+/// Generates the edges of the tile and sends them in batches.
+pub fn worker<M: EdgeModel>(
     sender: Sender<Vec<(u64, u64)>>,
     start: (u64, u64),
     end: (u64, u64),
-    params: &GenerationParameters<VecSeeds>,
+    params: &GenerationParameters<VecSeeds, M>,
+    nodes: &NodeSource<M>,
+) {
+    info!("Job: {:?} -> {:?}", start, end);
+    let tile_nodes = nodes.for_tile(start, end);
+    let nodes = tile_nodes.as_ref().unwrap_or(nodes);
+    send_buffered(&sender, params.edgebuffer_size, |cb| {
+        if params.skip_sampling {
+            crate::skip::skip_worker_function(start, end, params, nodes, cb)
+        } else if crate::simd::supports(params) {
+            crate::simd::worker_function(start, end, params, nodes, cb)
+        } else {
+            crate::worker_function(start, end, params, nodes, cb)
+        }
+    });
+    info!("Job done!");
+}
+
+/// Collects the edges produced by `f` in batches of `edgebuffer_size` and sends them.
+pub fn send_buffered<F: FnOnce(&mut dyn FnMut(u64, u64))>(
+    sender: &Sender<Vec<(u64, u64)>>,
+    edgebuffer_size: u64,
+    f: F,
 ) {
     let mut pair_queue = Vec::new();
-    pair_queue.resize(params.edgebuffer_size as usize, (0, 0));
+    pair_queue.resize(edgebuffer_size as usize, (0, 0));
     let mut pair_queue_index = 0usize;
     let mut pair_queue_sends = 0usize;
 
-    info!("Job: {:?} -> {:?}", start, end);
-    crate::worker_function(start, end, params, |i, j| {
+    f(&mut |i, j| {
         pair_queue[pair_queue_index] = (i, j);
         pair_queue_index += 1;
 
@@ -142,17 +163,652 @@ pub fn worker(
     }
 
     if pair_queue_sends > 1 {
-        warn!("Edge buffer likely too small. Had to send more than one for this job. Consider increasing the edgebuffer size to {}.", params.edgebuffer_size as usize * pair_queue_sends);
+        warn!("Edge buffer likely too small. Had to send more than one for this job. Consider increasing the edgebuffer size to {}.", edgebuffer_size as usize * pair_queue_sends);
     }
+}
 
-    info!("Job done!");
+/// The weights and positions of a range of nodes, usually all nodes computed once and shared by the workers.
+///
+/// The layout matches [compute_interleaved_variables](GenerationParametersExt::compute_interleaved_variables):
+/// the weight of every node is followed by its position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PregeneratedNodes {
+    /// The first node in the table.
+    first: u64,
+    /// Number of values per node, the weight and one per dimension.
+    stride: usize,
+    /// The interleaved weights and positions.
+    values: Vec<f32>,
+}
+
+impl PregeneratedNodes {
+    /// Computes the weights and positions of all nodes, see [compute_interleaved_variables](GenerationParametersExt::compute_interleaved_variables).
+    pub fn new<M: EdgeModel>(params: &GenerationParameters<VecSeeds, M>) -> Self {
+        Self {
+            first: 0,
+            stride: params.num_dimensions() + 1,
+            values: params.compute_interleaved_variables(),
+        }
+    }
+
+    /// Computes the weights and positions of the given nodes.
+    pub fn range<M: EdgeModel>(
+        params: &GenerationParameters<VecSeeds, M>,
+        nodes: Range<u64>,
+    ) -> Self {
+        let stride = params.num_dimensions() + 1;
+        let mut values = vec![0.0f32; (nodes.end - nodes.start) as usize * stride];
+        for (j, node) in nodes.clone().zip(values.chunks_exact_mut(stride)) {
+            node[0] = params.node_weight(j);
+            params.fill_node_position(j, &mut node[1..]);
+        }
+        Self {
+            first: nodes.start,
+            stride,
+            values,
+        }
+    }
+
+    /// The table to construct the [CPUGenerator] and [BKLGenerator](crate::bkl::BKLGenerator) workers with,
+    /// [None] if the parameters do not ask for pregenerated numbers.
+    pub fn shared<M: EdgeModel>(params: &GenerationParameters<VecSeeds, M>) -> Option<Arc<Self>> {
+        (params.random_mode == RandomMode::PreGenerate).then(|| Arc::new(Self::new(params)))
+    }
+
+    /// Number of nodes in the table.
+    pub fn len(&self) -> usize {
+        self.values.len() / self.stride
+    }
+
+    /// Whether the table holds no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Whether node j is in the table.
+    #[inline]
+    pub fn contains(&self, j: u64) -> bool {
+        j >= self.first && j - self.first < self.len() as u64
+    }
+
+    /// Weight of node j.
+    #[inline]
+    pub fn weight(&self, j: u64) -> f32 {
+        self.values[(j - self.first) as usize * self.stride]
+    }
+
+    /// Position of node j.
+    #[inline]
+    pub fn position(&self, j: u64) -> &[f32] {
+        let start = (j - self.first) as usize * self.stride + 1;
+        &self.values[start..start + self.stride - 1]
+    }
+}
+
+/// Provides the weights and positions of the nodes, pregenerated, cached per tile or on demand.
+pub struct NodeSource<'a, M: EdgeModel> {
+    /// The parameters to compute the nodes on demand from.
+    params: &'a GenerationParameters<VecSeeds, M>,
+    /// The pregenerated nodes, if the parameters ask for them.
+    pregenerated: Option<Arc<PregeneratedNodes>>,
+    /// The nodes of the rows and of the columns of a tile, see [for_tile](Self::for_tile).
+    tile: Option<[PregeneratedNodes; 2]>,
+}
+
+impl<'a, M: EdgeModel> NodeSource<'a, M> {
+    /// Computes the nodes up front if the parameters ask for pregenerated numbers.
+    pub fn new(params: &'a GenerationParameters<VecSeeds, M>) -> Self {
+        Self {
+            params,
+            pregenerated: PregeneratedNodes::shared(params),
+            tile: None,
+        }
+    }
+
+    /// Uses the given pregenerated nodes, computing them only if the parameters ask for them and none are given.
+    ///
+    /// Fails if the given nodes do not match the parameters.
+    pub fn with_pregenerated(
+        params: &'a GenerationParameters<VecSeeds, M>,
+        pregenerated: Option<Arc<PregeneratedNodes>>,
+    ) -> anyhow::Result<Self> {
+        if params.random_mode != RandomMode::PreGenerate {
+            return Ok(Self {
+                params,
+                pregenerated: None,
+                tile: None,
+            });
+        }
+        match pregenerated {
+            Some(nodes) => {
+                anyhow::ensure!(
+                    nodes.first == 0 && nodes.len() as u64 == params.v && nodes.stride == params.num_dimensions() + 1,
+                    "The pregenerated nodes ({} nodes in {} dimensions) do not match the parameters ({} nodes in {} dimensions).",
+                    nodes.len(),
+                    nodes.stride - 1,
+                    params.v,
+                    params.num_dimensions()
+                );
+                Ok(Self {
+                    params,
+                    pregenerated: Some(nodes),
+                    tile: None,
+                })
+            }
+            None => Ok(Self::new(params)),
+        }
+    }
+
+    /// The nodes of the tile from `start` to `end`, computed once for all its pairs.
+    ///
+    /// [None] unless the parameters ask for [RandomMode::TileCached].
+    /// The memory is proportional to the tile size, not to the number of vertices.
+    pub fn for_tile(&self, start: (u64, u64), end: (u64, u64)) -> Option<Self> {
+        if self.params.random_mode != RandomMode::TileCached {
+            return None;
+        }
+        let v = self.params.v;
+        let rows = PregeneratedNodes::range(self.params, start.0.min(v)..end.0.min(v));
+        let columns = PregeneratedNodes::range(self.params, start.1.min(v)..end.1.min(v));
+        Some(Self {
+            params: self.params,
+            pregenerated: None,
+            tile: Some([rows, columns]),
+        })
+    }
+
+    /// The table that holds node j, if any.
+    #[inline]
+    fn table(&self, j: u64) -> Option<&PregeneratedNodes> {
+        match (self.pregenerated.as_deref(), self.tile.as_ref()) {
+            (Some(nodes), _) => Some(nodes),
+            (None, Some(tile)) => tile.iter().find(|nodes| nodes.contains(j)),
+            (None, None) => None,
+        }
+    }
+
+    /// Weight of node j.
+    #[inline]
+    fn weight(&self, j: u64) -> f32 {
+        match self.table(j) {
+            Some(nodes) => nodes.weight(j),
+            None => self.params.node_weight(j),
+        }
+    }
+
+    /// Writes the position of node j into `p`.
+    #[inline]
+    fn fill_position(&self, j: u64, p: &mut [f32]) {
+        match self.table(j) {
+            Some(nodes) => p.copy_from_slice(nodes.position(j)),
+            None => self.params.fill_node_position(j, p),
+        }
+    }
+
+    /// Position of node j, computed into `scratch` if it is not in a table.
+    #[inline]
+    fn position<'b>(&'b self, j: u64, scratch: &'b mut [f32]) -> &'b [f32] {
+        match self.table(j) {
+            Some(nodes) => nodes.position(j),
+            None => {
+                self.params.fill_node_position(j, scratch);
+                scratch
+            }
+        }
+    }
+}
+
+/// RNG for the samplers that do not use the per-pair hash.
+///
+/// It is seeded per tile, such that the result does not depend on the order in which the tiles are processed.
+pub fn tile_rng<M: EdgeModel>(
+    start: (u64, u64),
+    params: &GenerationParameters<VecSeeds, M>,
+) -> StdRng {
+    StdRng::seed_from_u64(
+        params
+            .hash
+            .hash64_3(start.0, start.1, params.get_seed(SeedEnum::Edge)),
+    )
+}
+
+/// Calls `f` for the candidates among `n` items, where every item is a candidate with probability `bound`.
+///
+/// The gaps between candidates are geometrically distributed, so only the candidates cost time.
+/// The caller then accepts a candidate with probability `p / bound`, which yields an edge with probability `p`.
+pub fn sample_candidates<F: FnMut(&mut StdRng, u64)>(
+    rng: &mut StdRng,
+    bound: f32,
+    n: u64,
+    mut f: F,
+) {
+    if bound.is_nan() || bound <= 0.0f32 {
+        return;
+    }
+    let log_skip = (-(bound as f64)).ln_1p();
+
+    let mut k = 0u64;
+    while k < n {
+        if bound < 1.0f32 {
+            let u: f64 = rng.gen();
+            let skip = (1.0f64 - u).ln() / log_skip;
+            if skip >= (n - k) as f64 {
+                break;
+            }
+            k += skip as u64;
+        }
+        f(rng, k);
+        k += 1;
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use generator_common::model::{ChungLu, Girg};
+    use generator_common::params::ext::GenerationParametersExt;
+    use generator_common::params::{
+        EdgeModel, EdgeRandomness, GenerationParameters, GenerationParametersBuilder, Geometry,
+        GraphKind, HashFunction, HyperbolicParameters, Model, NodeAttributes, RandomMode,
+        SeedGettable, VecSeeds,
+    };
+    use generator_common::random::ParetoDistribution;
+
     #[test]
     fn it_works() {
         let result = 2 + 2;
         assert_eq!(result, 4);
     }
+
+    #[test]
+    fn undirected_only_emits_upper_pairs() {
+        let params = GenerationParametersBuilder::new()
+            .vertices(500)
+            .seeds(&[1, 2, 3, 4])
+            .tile_size(64)
+            .graph_kind(GraphKind::Undirected)
+            .build()
+            .unwrap();
+
+        let nodes = crate::NodeSource::new(&params);
+        let mut edges = Vec::new();
+        for (start, end) in params.tiles() {
+            crate::worker_function(start, end, &params, &nodes, |i, j| edges.push((i, j)));
+        }
+
+        assert!(!edges.is_empty(), "expected some edges");
+        for (i, j) in edges.iter() {
+            assert!(i < j, "edge ({}, {}) is not in the upper triangle", i, j);
+        }
+        let mut deduped = edges.clone();
+        deduped.sort_unstable();
+        deduped.dedup();
+        assert_eq!(deduped.len(), edges.len(), "expected no duplicate edges");
+    }
+
+    #[test]
+    fn hyperbolic_threshold_connects_close_nodes() {
+        let h = HyperbolicParameters {
+            radius: 2.0 * (400.0f32).ln(),
+            alpha: 0.75,
+            temperature: 0.0,
+        };
+        let params = GenerationParametersBuilder::new()
+            .dimensions(1)
+            .vertices(400)
+            .seeds(&[1, 2, 3])
+            .tile_size(64)
+            .graph_kind(GraphKind::Undirected)
+            .model(Model::Hyperbolic(h))
+            .build()
+            .unwrap();
+
+        let nodes = crate::NodeSource::new(&params);
+        let mut edges = Vec::new();
+        for (start, end) in params.tiles() {
+            crate::worker_function(start, end, &params, &nodes, |i, j| edges.push((i, j)));
+        }
+        edges.sort_unstable();
+
+        let radii = params.compute_weights();
+        let angles = params.compute_positions();
+        let mut expected = Vec::new();
+        for j in 0..params.v {
+            for i in 0..j {
+                let d = generator_common::algorithm::compute_hyperbolic_distance(
+                    radii[i as usize],
+                    radii[j as usize],
+                    &angles[i as usize],
+                    &angles[j as usize],
+                );
+                if d <= h.radius {
+                    expected.push((i, j));
+                }
+            }
+        }
+        expected.sort_unstable();
+
+        assert!(!edges.is_empty(), "expected some edges");
+        assert_eq!(edges, expected);
+    }
+
+    /// Erdős–Rényi graph, as an example of a model that lives outside of the core.
+    #[derive(Copy, Clone, Debug)]
+    struct ErdosRenyi(f32);
+
+    impl EdgeModel for ErdosRenyi {
+        fn probability<S: SeedGettable>(
+            &self,
+            _w_i: f32,
+            _w_j: f32,
+            _p_i: &[f32],
+            _p_j: &[f32],
+            _params: &GenerationParameters<S, Self>,
+        ) -> f32 {
+            self.0
+        }
+    }
+
+    fn all_edges<M: EdgeModel>(params: &GenerationParameters<VecSeeds, M>) -> Vec<(u64, u64)> {
+        let nodes = crate::NodeSource::new(params);
+        let mut edges = Vec::new();
+        for (start, end) in params.tiles() {
+            crate::worker_function(start, end, params, &nodes, |i, j| edges.push((i, j)));
+        }
+        edges.sort_unstable();
+        edges
+    }
+
+    #[test]
+    fn custom_model() {
+        let params = GenerationParametersBuilder::new()
+            .vertices(500)
+            .seeds(&[1, 2, 3, 4])
+            .tile_size(64)
+            .build()
+            .unwrap()
+            .with_model(ErdosRenyi(0.1));
+
+        let edges = all_edges(&params).len() as f64;
+        let expected = 0.1 * 500.0 * 500.0;
+        assert!(
+            (edges - expected).abs() < 0.05 * expected,
+            "{} edges, expected about {}",
+            edges,
+            expected
+        );
+    }
+
+    #[test]
+    fn model_enum_matches_models() {
+        let params = GenerationParametersBuilder::new()
+            .vertices(500)
+            .seeds(&[1, 2, 3, 4])
+            .tile_size(64)
+            .random_mode(RandomMode::OnDemand)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            all_edges(&params),
+            all_edges(&params.clone().with_model(Girg))
+        );
+
+        let chung_lu = all_edges(&params.clone().with_model(Model::ChungLu));
+        assert!(!chung_lu.is_empty(), "expected some edges");
+        assert_eq!(chung_lu, all_edges(&params.with_model(ChungLu)));
+    }
+
+    #[test]
+    fn calibrated_average_degree() {
+        let params = GenerationParametersBuilder::new()
+            .weights(ParetoDistribution::new(1.0, 2.5))
+            .alpha(2.5)
+            .seeds(&[9, 10, 11, 12])
+            .graph_kind(GraphKind::Undirected)
+            .avg_degree(8.0)
+            .build()
+            .unwrap();
+
+        let degree = 2.0 * all_edges(&params).len() as f64 / params.v as f64;
+        assert!((degree - 8.0).abs() < 0.8, "average degree {}", degree);
+    }
+
+    #[test]
+    fn bits64_edge_randomness() {
+        let mut params = GenerationParametersBuilder::new()
+            .weights(ParetoDistribution::new(1.0, 2.5))
+            .alpha(2.5)
+            .seeds(&[9, 10, 11, 12])
+            .tile_size(250)
+            .graph_kind(GraphKind::Undirected)
+            .build()
+            .unwrap();
+        let bits32 = all_edges(&params);
+        params.edge_randomness = EdgeRandomness::Bits64;
+        let bits64 = all_edges(&params);
+
+        assert_ne!(bits32, bits64);
+        let ratio = bits64.len() as f64 / bits32.len() as f64;
+        assert!(
+            (ratio - 1.0).abs() < 0.1,
+            "{} vs {} edges",
+            bits64.len(),
+            bits32.len()
+        );
+        assert!(bits64.iter().all(|(i, j)| i < j));
+    }
+
+    #[test]
+    fn hash_families_keep_distribution() {
+        // Summed over a few seeds, so the tolerance only has to cover the remaining sampling noise.
+        let count = |hash: HashFunction| -> (usize, Vec<(u64, u64)>) {
+            let mut total = 0;
+            let mut first = Vec::new();
+            for seed in 0..4u64 {
+                let params = GenerationParametersBuilder::new()
+                    .weights(ParetoDistribution::new(1.0, 2.5))
+                    .alpha(2.5)
+                    .vertices(1000)
+                    .seed(seed)
+                    .tile_size(250)
+                    .hash(hash)
+                    .build()
+                    .unwrap();
+                let edges = all_edges(&params);
+                total += edges.len();
+                if seed == 0 {
+                    first = edges;
+                }
+            }
+            (total, first)
+        };
+
+        let (murmur3, murmur3_edges) = count(HashFunction::Murmur3);
+        for hash in [
+            HashFunction::SplitMix64,
+            HashFunction::Xxh3,
+            HashFunction::Philox,
+        ] {
+            let (total, edges) = count(hash);
+            assert_ne!(edges, murmur3_edges);
+            let ratio = total as f64 / murmur3 as f64;
+            assert!(
+                (ratio - 1.0).abs() < 0.05,
+                "{:?}: {} vs {} edges",
+                hash,
+                total,
+                murmur3
+            );
+        }
+    }
+
+    #[test]
+    fn loaded_nodes_match_generated_nodes() {
+        let params = GenerationParametersBuilder::new()
+            .vertices(300)
+            .seeds(&[1, 2, 3, 4])
+            .tile_size(64)
+            .random_mode(RandomMode::OnDemand)
+            .build()
+            .unwrap();
+        let nodes = NodeAttributes::new(
+            Some(params.compute_weights()),
+            Some(params.compute_positions()),
+        )
+        .unwrap();
+
+        // Different seeds for the attributes, the edges must still be the same.
+        let loaded = GenerationParametersBuilder::new()
+            .weights(ParetoDistribution::new(5.0, 3.0))
+            .vertices(10)
+            .seeds(&[1, 2, 30, 40])
+            .tile_size(64)
+            .random_mode(RandomMode::OnDemand)
+            .build()
+            .unwrap()
+            .with_nodes(nodes)
+            .unwrap();
+
+        assert_eq!(loaded.v, params.v);
+        assert_eq!(loaded.w, params.w);
+        assert_eq!(loaded.random_mode, RandomMode::PreGenerate);
+        assert_eq!(all_edges(&loaded), all_edges(&params));
+
+        let three_dims = NodeAttributes::new(None, Some(vec![vec![0.5; 3]; 10])).unwrap();
+        assert!(params.with_nodes(three_dims).is_err());
+    }
+
+    /// Average out-degree of the nodes near the border and of the nodes in the center.
+    fn border_and_center_degrees(params: &GenerationParameters<VecSeeds>) -> (f64, f64) {
+        let nodes = crate::NodeSource::new(params);
+        let mut degrees = vec![0usize; params.v as usize];
+        for (start, end) in params.tiles() {
+            crate::worker_function(start, end, params, &nodes, |i, _| degrees[i as usize] += 1);
+        }
+
+        let (mut border, mut center) = ((0usize, 0usize), (0usize, 0usize));
+        for (i, p) in params.compute_positions().iter().enumerate() {
+            if p.iter().any(|x| *x < 0.1 || *x > 0.9) {
+                border = (border.0 + degrees[i], border.1 + 1);
+            } else if p.iter().all(|x| *x > 0.3 && *x < 0.7) {
+                center = (center.0 + degrees[i], center.1 + 1);
+            }
+        }
+
+        (
+            border.0 as f64 / border.1 as f64,
+            center.0 as f64 / center.1 as f64,
+        )
+    }
+
+    #[test]
+    fn cube_lowers_border_degree() {
+        let mut params = GenerationParametersBuilder::new()
+            .weights(ParetoDistribution::new(3.0, 10.0))
+            .alpha(2.5)
+            .vertices(1500)
+            .seeds(&[5, 6, 7, 8])
+            .tile_size(1500)
+            .build()
+            .unwrap();
+
+        let (torus_border, torus_center) = border_and_center_degrees(&params);
+        params.geometry = Geometry::Cube;
+        let (cube_border, cube_center) = border_and_center_degrees(&params);
+
+        assert!(
+            (torus_border / torus_center - 1.0).abs() < 0.1,
+            "torus should have no boundary effects: {} vs {}",
+            torus_border,
+            torus_center
+        );
+        assert!(
+            cube_border < 0.85 * cube_center,
+            "cube border nodes should have a lower degree: {} vs {}",
+            cube_border,
+            cube_center
+        );
+    }
+
+    #[test]
+    fn edge_iterator_matches_worker_function() {
+        let params = GenerationParametersBuilder::new()
+            .vertices(700)
+            .seeds(&[9, 8, 7, 6])
+            .tile_size(100)
+            .build()
+            .unwrap();
+        let mut edges = generator_common::generate_edges::<crate::CPUGenerator, _>(
+            &params,
+            crate::PregeneratedNodes::shared(&params),
+            3,
+        )
+        .collect::<anyhow::Result<Vec<_>>>()
+        .unwrap();
+        edges.sort_unstable();
+        assert_eq!(edges, all_edges(&params));
+    }
+
+    #[test]
+    fn pregenerated_nodes_match_interleaved_variables() {
+        let params = GenerationParametersBuilder::new()
+            .dimensions(3)
+            .vertices(50)
+            .seeds(&[1, 2, 3, 4, 5])
+            .tile_size(10)
+            .build()
+            .unwrap();
+        let nodes = crate::PregeneratedNodes::new(&params);
+        assert_eq!(nodes.len(), 50);
+        assert_eq!(nodes.values, params.compute_interleaved_variables());
+        assert_eq!(nodes.weight(7), params.node_weight(7));
+        assert_eq!(nodes.position(7), params.compute_position(7).as_slice());
+
+        let mut other = params.clone();
+        other.v = 60;
+        assert!(crate::NodeSource::with_pregenerated(
+            &other,
+            crate::PregeneratedNodes::shared(&params)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn tile_cached_numbers_match_other_modes() {
+        for (graph_kind, skip_sampling) in [
+            (GraphKind::Directed, false),
+            (GraphKind::Undirected, false),
+            (GraphKind::Directed, true),
+        ] {
+            let edges = |random_mode: RandomMode| {
+                let params = GenerationParametersBuilder::new()
+                    .vertices(450)
+                    .seeds(&[4, 3, 2, 1])
+                    .tile_size(100)
+                    .random_mode(random_mode)
+                    .graph_kind(graph_kind)
+                    .skip_sampling(skip_sampling)
+                    .build()
+                    .unwrap();
+                let mut edges = generator_common::generate_edges::<crate::CPUGenerator, _>(
+                    &params,
+                    crate::PregeneratedNodes::shared(&params),
+                    2,
+                )
+                .collect::<anyhow::Result<Vec<_>>>()
+                .unwrap();
+                edges.sort_unstable();
+                edges
+            };
+            let tile_cached = edges(RandomMode::TileCached);
+            assert!(!tile_cached.is_empty());
+            assert_eq!(
+                tile_cached,
+                edges(RandomMode::PreGenerate),
+                "{:?}",
+                graph_kind
+            );
+            assert_eq!(tile_cached, edges(RandomMode::OnDemand), "{:?}", graph_kind);
+        }
+    }
 }
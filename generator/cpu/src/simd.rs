@@ -0,0 +1,399 @@
+//! Vectorized variant of [worker_function](crate::worker_function) for the [Girg](generator_common::model::Girg) model.
+//!
+//! For every node j of a tile, the kernel tests [LANES] nodes i at once with [std::simd]:
+//! the per-dimension distances, the norm, the probability and the Murmur3 hashes of the pairs are computed for all lanes together.
+//! Only `powf`, `sqrt` and the conversion of the hashes to floats are done lane by lane, with the same operations as the scalar path.
+//! The edges are thus bit-identical to those of [worker_function](crate::worker_function), and emitted in the same order.
+//!
+//! The kernel is used for the parameters accepted by [supports], the others fall back to the scalar path.
+//! On x86_64 the kernel is also compiled for AVX2, which is used if the CPU supports it.
+
+use crate::NodeSource;
+use generator_common::params::{
+    EdgeModel, EdgeRandomness, GenerationParameters, Geometry, HashFunction, Norm, SeedEnum,
+    VecSeeds,
+};
+use std::simd::Simd;
+
+/// Number of pairs tested at once.
+pub const LANES: usize = 8;
+
+/// A vector of floats, one per lane.
+type F32s = Simd<f32, LANES>;
+/// A vector of 32-bit integers, one per lane.
+type U32s = Simd<u32, LANES>;
+
+/// Whether the vectorized kernel supports the parameters.
+///
+/// This requires the [Girg](generator_common::model::Girg) model with the [Murmur3](HashFunction::Murmur3) hash and [32-bit edge randomness](EdgeRandomness::Bits32).
+pub fn supports<M: EdgeModel>(params: &GenerationParameters<VecSeeds, M>) -> bool {
+    params.model.is_girg()
+        && params.hash == HashFunction::Murmur3
+        && params.edge_randomness == EdgeRandomness::Bits32
+}
+
+/// Calls `cb` for every edge (i, j) of the tile, like [worker_function](crate::worker_function).
+///
+/// The parameters must be [supported](supports).
+pub fn worker_function<M: EdgeModel, F: FnMut(u64, u64)>(
+    start: (u64, u64),
+    end: (u64, u64),
+    params: &GenerationParameters<VecSeeds, M>,
+    nodes: &NodeSource<M>,
+    cb: F,
+) {
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx2") {
+        // Safety: the CPU supports AVX2.
+        return unsafe { worker_function_avx2(start, end, params, nodes, cb) };
+    }
+    kernel(start, end, params, nodes, cb)
+}
+
+/// [kernel] compiled for AVX2.
+///
+/// # Safety
+/// The CPU must support AVX2.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn worker_function_avx2<M: EdgeModel, F: FnMut(u64, u64)>(
+    start: (u64, u64),
+    end: (u64, u64),
+    params: &GenerationParameters<VecSeeds, M>,
+    nodes: &NodeSource<M>,
+    cb: F,
+) {
+    kernel(start, end, params, nodes, cb)
+}
+
+/// The weights and positions of the rows of a tile, one vector per dimension, padded to a multiple of [LANES].
+struct Rows {
+    /// The weights.
+    weights: Vec<f32>,
+    /// The coordinates of the positions, one vector per dimension.
+    positions: Vec<Vec<f32>>,
+}
+
+impl Rows {
+    /// Collects the nodes `first..last`.
+    fn new<M: EdgeModel>(first: u64, last: u64, dims: usize, nodes: &NodeSource<M>) -> Self {
+        let padded = ((last - first) as usize + LANES - 1) / LANES * LANES;
+        let mut weights = vec![0.0f32; padded];
+        let mut positions = vec![vec![0.0f32; padded]; dims];
+        let mut p = vec![0.0f32; dims];
+        for (k, i) in (first..last).enumerate() {
+            weights[k] = nodes.weight(i);
+            nodes.fill_position(i, &mut p);
+            for (d, x) in p.iter().enumerate() {
+                positions[d][k] = *x;
+            }
+        }
+        Self { weights, positions }
+    }
+}
+
+/// Applies `f` to every lane.
+#[inline(always)]
+fn per_lane<F: Fn(f32) -> f32>(x: F32s, f: F) -> F32s {
+    let mut x = x.to_array();
+    for v in x.iter_mut() {
+        *v = f(*v);
+    }
+    F32s::from_array(x)
+}
+
+/// The tile loop, inlined into the variants for the different target features.
+#[inline(always)]
+fn kernel<M: EdgeModel, F: FnMut(u64, u64)>(
+    start: (u64, u64),
+    end: (u64, u64),
+    params: &GenerationParameters<VecSeeds, M>,
+    nodes: &NodeSource<M>,
+    mut cb: F,
+) {
+    let first = start.0;
+    let last = params.v.min(end.0);
+    if first >= last {
+        return;
+    }
+    let dims = params.num_dimensions();
+    let rows = Rows::new(first, last, dims, nodes);
+    let seed = params.get_seed(SeedEnum::Edge);
+    let mut p_j_prime = vec![0.0f32; dims];
+
+    let zero = F32s::splat(0.0);
+    let one = F32s::splat(1.0);
+    let c = F32s::splat(params.c);
+    let w = F32s::splat(params.w);
+    let threshold_exponent = 1.0f32 / dims as f32;
+    let distance_exponent = params.alpha * dims as f32;
+
+    for j in start.1..params.v.min(end.1) {
+        let w_j = F32s::splat(nodes.weight(j));
+        let p_j = nodes.position(j, &mut p_j_prime);
+        let hash_j = Murmur3Suffix::new(j, seed);
+
+        for k in (0..rows.weights.len()).step_by(LANES) {
+            let i = first + k as u64;
+            // Only the pairs with i < j are candidates of undirected graphs, see [GenerationParameters::is_candidate_pair].
+            if !params.is_candidate_pair(i, j) {
+                break;
+            }
+
+            // The distance, see [compute_distance](generator_common::algorithm::compute_distance).
+            let mut acc = zero;
+            for (positions, x_j) in rows.positions.iter().zip(p_j.iter()) {
+                let x = (F32s::from_slice(&positions[k..k + LANES]) - F32s::splat(*x_j)).abs();
+                let x = match params.geometry {
+                    Geometry::Torus => x.min(one - x),
+                    Geometry::Cube => x,
+                };
+                acc = match params.norm {
+                    Norm::L1 => acc + x,
+                    Norm::L2 => acc + x * x,
+                    Norm::Lp(p) => acc + per_lane(x, |x| x.powf(p)),
+                    Norm::Max => acc.max(x),
+                };
+            }
+            let d = match params.norm {
+                Norm::L1 | Norm::Max => acc,
+                Norm::L2 => per_lane(acc, f32::sqrt),
+                Norm::Lp(p) => {
+                    let e = 1.0f32 / p;
+                    per_lane(acc, |x| x.powf(e))
+                }
+            };
+
+            // The probability, see [compute_probability](generator_common::algorithm::compute_probability).
+            let w_i = F32s::from_slice(&rows.weights[k..k + LANES]);
+            let p = if params.alpha.is_infinite() {
+                let v = per_lane(c * (w_i * w_j) / w, |x| x.powf(threshold_exponent));
+                d.lanes_le(v).select(one, zero)
+            } else {
+                let weights = per_lane((w_i * w_j) / w, |x| x.powf(params.alpha));
+                let distances = per_lane(d, |x| x.powf(distance_exponent));
+                (c * weights / distances).min(one)
+            };
+
+            // The random numbers, see [random_edge](generator_common::random::random_edge).
+            // Undirected candidates have i < j, so the symmetric variant hashes (i, j) as well.
+            let mut s1 = [0u64; LANES];
+            for (l, s) in s1.iter_mut().enumerate() {
+                *s = i + l as u64;
+            }
+            let hashes = hash_j.hash(s1).to_array();
+            let p = p.to_array();
+            for l in 0..LANES.min((last - i) as usize) {
+                let r = (hashes[l] as f64 / u32::MAX as f64) as f32;
+                if p[l] > r && params.is_candidate_pair(i + l as u64, j) {
+                    cb(i + l as u64, j)
+                }
+            }
+        }
+    }
+}
+
+/// Constants of [murmur3_32_3](generator_common::random::murmur3::murmur3_32_3).
+mod murmur3 {
+    pub const C1: u32 = 0x85eb_ca6b;
+    pub const C2: u32 = 0xc2b2_ae35;
+    pub const R1: u32 = 16;
+    pub const R2: u32 = 13;
+    pub const M: u32 = 5;
+    pub const N: u32 = 0xe654_6b64;
+    pub const K_C1: u32 = 0xcc9e_2d51;
+    pub const K_C2: u32 = 0x1b87_3593;
+    pub const K_R1: u32 = 15;
+}
+
+/// Rotates every lane left by `r` bits.
+#[inline(always)]
+fn rotate_left(x: U32s, r: u32) -> U32s {
+    (x << U32s::splat(r)) | (x >> U32s::splat(32 - r))
+}
+
+/// Mixes the 32-bit words `k` into the Murmur3 `state`.
+#[inline(always)]
+fn murmur3_round(state: U32s, k: U32s) -> U32s {
+    use murmur3::*;
+    let k = rotate_left(k * U32s::splat(K_C1), K_R1) * U32s::splat(K_C2);
+    let state = rotate_left(state ^ k, R2);
+    state * U32s::splat(M) + U32s::splat(N)
+}
+
+/// The two 32-bit words Murmur3 reads from each number, in the byte order of [murmur3_32_3](generator_common::random::murmur3::murmur3_32_3).
+#[inline(always)]
+fn words(s: [u64; LANES]) -> [U32s; 2] {
+    let mut high = [0u32; LANES];
+    let mut low = [0u32; LANES];
+    for l in 0..LANES {
+        high[l] = ((s[l] >> 32) as u32).swap_bytes();
+        low[l] = (s[l] as u32).swap_bytes();
+    }
+    [U32s::from_array(high), U32s::from_array(low)]
+}
+
+/// Batched [murmur3_32_3](generator_common::random::murmur3::murmur3_32_3), hashes the triples `(s1[l], s2[l], s3[l])` of all lanes.
+pub fn murmur3_32_3(s1: [u64; LANES], s2: [u64; LANES], s3: [u64; LANES]) -> [u32; LANES] {
+    let mut state = U32s::splat(0);
+    for s in [s1, s2, s3] {
+        for k in words(s) {
+            state = murmur3_round(state, k);
+        }
+    }
+    murmur3_finish(state).to_array()
+}
+
+/// The finalization of Murmur3 for three 64-bit numbers.
+#[inline(always)]
+fn murmur3_finish(state: U32s) -> U32s {
+    use murmur3::*;
+    let mut hash = state ^ U32s::splat(24);
+    hash ^= hash >> U32s::splat(R1);
+    hash *= U32s::splat(C1);
+    hash ^= hash >> U32s::splat(R2);
+    hash *= U32s::splat(C2);
+    hash ^= hash >> U32s::splat(R1);
+    hash
+}
+
+/// Murmur3 of `(s1, s2, s3)` for varying `s1` and fixed `s2` and `s3`, with the words of the fixed numbers prepared once.
+struct Murmur3Suffix {
+    /// The words of `s2` and `s3`.
+    words: [U32s; 4],
+}
+
+impl Murmur3Suffix {
+    /// Prepares the hashes of `(_, s2, s3)`.
+    #[inline(always)]
+    fn new(s2: u64, s3: u64) -> Self {
+        let [a, b] = words([s2; LANES]);
+        let [c, d] = words([s3; LANES]);
+        Self {
+            words: [a, b, c, d],
+        }
+    }
+
+    /// Hashes `(s1[l], s2, s3)` for all lanes.
+    #[inline(always)]
+    fn hash(&self, s1: [u64; LANES]) -> U32s {
+        let mut state = U32s::splat(0);
+        for k in words(s1).iter().chain(self.words.iter()) {
+            state = murmur3_round(state, *k);
+        }
+        murmur3_finish(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generator_common::model::ChungLu;
+    use generator_common::params::ext::GenerationParametersExt;
+    use generator_common::params::{GenerationParametersBuilder, GraphKind, Model, RandomMode};
+    use generator_common::random::ParetoDistribution;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn batched_murmur3_matches_scalar() {
+        let mut rng = StdRng::seed_from_u64(23);
+        for _ in 0..1000 {
+            let s: [[u64; LANES]; 3] = rng.gen();
+            let expected = (0..LANES)
+                .map(|l| generator_common::random::murmur3::murmur3_32_3(s[0][l], s[1][l], s[2][l]))
+                .collect::<Vec<_>>();
+            assert_eq!(murmur3_32_3(s[0], s[1], s[2]).to_vec(), expected);
+            let suffix = Murmur3Suffix::new(s[1][0], s[2][0]);
+            for l in 0..LANES {
+                assert_eq!(
+                    suffix.hash(s[0]).to_array()[l],
+                    generator_common::random::murmur3::murmur3_32_3(s[0][l], s[1][0], s[2][0])
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn kernel_matches_scalar_path() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..40 {
+            let dims = rng.gen_range(1..=4);
+            let alpha = if rng.gen_bool(0.3) {
+                f32::INFINITY
+            } else {
+                rng.gen_range(1.1..4.0)
+            };
+            let params = GenerationParametersBuilder::new()
+                .dimensions(dims)
+                .weights(ParetoDistribution::new(1.0, rng.gen_range(2.1..3.0)))
+                .alpha(alpha)
+                .vertices(rng.gen_range(20..300))
+                .seeds(&(0..dims + 2).map(|_| rng.gen()).collect::<Vec<_>>())
+                .tile_size(rng.gen_range(1..70))
+                .random_mode(if rng.gen() {
+                    RandomMode::PreGenerate
+                } else {
+                    RandomMode::OnDemand
+                })
+                .graph_kind(if rng.gen() {
+                    GraphKind::Directed
+                } else {
+                    GraphKind::Undirected
+                })
+                .norm(match rng.gen_range(0..4u32) {
+                    0 => Norm::L1,
+                    1 => Norm::L2,
+                    2 => Norm::Lp(rng.gen_range(1.0..4.0)),
+                    _ => Norm::Max,
+                })
+                .geometry(if rng.gen() {
+                    Geometry::Torus
+                } else {
+                    Geometry::Cube
+                })
+                // Large constants give dense graphs, such that many probabilities are neither 0 nor 1.
+                .scaling(rng.gen_range(0.01..100.0))
+                .build()
+                .unwrap();
+            assert!(supports(&params));
+
+            let nodes = NodeSource::new(&params);
+            let mut total = 0;
+            for (start, end) in params.tiles() {
+                let mut scalar = Vec::new();
+                crate::worker_function(start, end, &params, &nodes, |i, j| scalar.push((i, j)));
+                let mut vectorized = Vec::new();
+                worker_function(start, end, &params, &nodes, |i, j| vectorized.push((i, j)));
+                let mut generic = Vec::new();
+                kernel(start, end, &params, &nodes, |i, j| generic.push((i, j)));
+                assert_eq!(scalar, vectorized, "{:?}", params);
+                assert_eq!(scalar, generic, "{:?}", params);
+                total += scalar.len();
+            }
+            assert!(total > 0, "{:?}", params);
+        }
+    }
+
+    #[test]
+    fn only_girg_with_murmur3_is_supported() {
+        let mut params = GenerationParametersBuilder::new()
+            .weights(ParetoDistribution::new(1.0, 2.5))
+            .vertices(100)
+            .seeds(&[1, 2, 3, 4])
+            .tile_size(10)
+            .random_mode(RandomMode::OnDemand)
+            .build()
+            .unwrap();
+        assert!(supports(&params));
+        params.edge_randomness = EdgeRandomness::Bits64;
+        assert!(!supports(&params));
+        params.edge_randomness = EdgeRandomness::Bits32;
+        params.hash = HashFunction::Xxh3;
+        assert!(!supports(&params));
+        params.hash = HashFunction::Murmur3;
+        params.model = Model::ChungLu;
+        assert!(!supports(&params));
+        assert!(!supports(&params.with_model(ChungLu)));
+    }
+}
@@ -0,0 +1,242 @@
+//! Geometric skip sampling within a tile.
+//!
+//! For sparse graphs almost all pairs in a tile have a tiny probability, so testing every pair wastes most of the time.
+//! In this mode the j-nodes of a tile are put in a uniform grid and the nodes of every cell are ordered by weight and split into blocks of similar weight.
+//! For a node i and a block, [EdgeModel::probability_bound] with the largest weight of the block and the distance from i to the cell gives an upper bound p̄ on the probabilities of all pairs.
+//! The candidates among the pairs of the block are found with geometric skips (see [sample_candidates]), which are then thinned with the exact probability.
+//!
+//! The edges follow the same distribution as those of [worker_function](crate::worker_function), but they are drawn from a seeded RNG per tile instead of the per-pair hash.
+//! Models without a probability bound are supported, but every pair becomes a candidate.
+//! The positions of the nodes must lie in the unit cube.
+
+use crate::{sample_candidates, tile_rng, NodeSource};
+use generator_common::params::{EdgeModel, GenerationParameters, VecSeeds};
+use rand::Rng;
+use std::cmp::Ordering;
+
+/// Average number of nodes per grid cell.
+const NODES_PER_CELL: f64 = 128.0;
+
+/// The distance from node i to a cell is reduced by this fraction, such that rounding can never make the bound smaller than the probability.
+const DISTANCE_MARGIN: f32 = 1e-4;
+
+/// A block of j-nodes in one cell with similar weights.
+struct Block {
+    /// Range of the block in the sorted nodes.
+    start: usize,
+    /// End of the range of the block in the sorted nodes.
+    end: usize,
+    /// Largest weight in the block.
+    max_weight: f32,
+}
+
+/// A non-empty grid cell.
+struct Cell {
+    /// Coordinates of the cell in the grid.
+    coords: Vec<u64>,
+    /// The weight blocks of the cell, in order of decreasing weight.
+    blocks: Vec<Block>,
+}
+
+/// Samples the edges (i, j) of the tile, with i in `[start.0, end.0)` and j in `[start.1, end.1)`, using geometric skips.
+pub fn skip_worker_function<M: EdgeModel, F: FnMut(u64, u64)>(
+    start: (u64, u64),
+    end: (u64, u64),
+    params: &GenerationParameters<VecSeeds, M>,
+    nodes: &NodeSource<M>,
+    mut cb: F,
+) {
+    let dims = params.num_dimensions();
+    let i_range = start.0..end.0.min(params.v);
+    let j_range = start.1..end.1.min(params.v);
+    if i_range.is_empty() || j_range.is_empty() {
+        return;
+    }
+
+    let cells_per_dim = ((j_range.end - j_range.start) as f64 / NODES_PER_CELL)
+        .powf(1.0 / dims as f64)
+        .floor()
+        .max(1.0) as u64;
+    let cell_side = 1.0f32 / cells_per_dim as f32;
+    let cell_coord = |x: f32| ((x.max(0.0) * cells_per_dim as f32) as u64).min(cells_per_dim - 1);
+
+    // Sort the j-nodes by cell, and by decreasing weight within a cell.
+    let mut p = vec![0.0f32; dims];
+    let mut js: Vec<(u64, f32, u64, Vec<f32>)> = j_range
+        .map(|j| {
+            nodes.fill_position(j, &mut p);
+            let cell = p
+                .iter()
+                .fold(0u64, |acc, x| acc * cells_per_dim + cell_coord(*x));
+            (cell, nodes.weight(j), j, p.clone())
+        })
+        .collect();
+    js.sort_unstable_by(|a, b| {
+        a.0.cmp(&b.0)
+            .then(b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal))
+    });
+
+    let mut cells: Vec<Cell> = Vec::new();
+    let mut k = 0;
+    while k < js.len() {
+        let cell = js[k].0;
+        let coords = js[k].3.iter().map(|x| cell_coord(*x)).collect();
+        let mut blocks = Vec::new();
+        while k < js.len() && js[k].0 == cell {
+            // A block holds the nodes with at least half the weight of its first node.
+            let start = k;
+            let max_weight = js[k].1;
+            while k < js.len() && js[k].0 == cell && js[k].1 >= 0.5 * max_weight {
+                k += 1;
+            }
+            blocks.push(Block {
+                start,
+                end: k,
+                max_weight,
+            });
+        }
+        cells.push(Cell { coords, blocks });
+    }
+
+    let mut rng = tile_rng(start, params);
+    let mut p_i = vec![0.0f32; dims];
+    for i in i_range {
+        let w_i = nodes.weight(i);
+        nodes.fill_position(i, &mut p_i);
+
+        for cell in cells.iter() {
+            // Lower bound on the distance from node i to any point in the cell.
+            let acc = p_i
+                .iter()
+                .zip(cell.coords.iter())
+                .fold(0.0f32, |acc, (x, c)| {
+                    let lo = *c as f32 * cell_side;
+                    let hi = lo + cell_side;
+                    let gap = if *x >= lo && *x <= hi {
+                        0.0f32
+                    } else {
+                        params
+                            .geometry
+                            .delta(*x, lo)
+                            .min(params.geometry.delta(*x, hi))
+                    };
+                    params.norm.accumulate(acc, gap)
+                });
+            let d_min = params.norm.finish(acc) * (1.0f32 - DISTANCE_MARGIN);
+
+            for block in cell.blocks.iter() {
+                let bound = params
+                    .model
+                    .probability_bound(w_i, block.max_weight, d_min, params)
+                    .unwrap_or(1.0f32)
+                    .min(1.0f32);
+                let n = (block.end - block.start) as u64;
+                sample_candidates(&mut rng, bound, n, |rng, k| {
+                    let (_, w_j, j, p_j) = &js[block.start + k as usize];
+                    if params.is_candidate_pair(i, *j) {
+                        let p = params.model.probability(w_i, *w_j, &p_i, p_j, params);
+                        if rng.gen::<f64>() * (bound as f64) < p as f64 {
+                            cb(i, *j);
+                        }
+                    }
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generator_common::params::ext::GenerationParametersExt;
+    use generator_common::params::{GenerationParametersBuilder, Geometry, GraphKind, Norm};
+    use generator_common::random::ParetoDistribution;
+
+    fn params(
+        alpha: f32,
+        graph_kind: GraphKind,
+        norm: Norm,
+        geometry: Geometry,
+    ) -> GenerationParameters<VecSeeds> {
+        GenerationParametersBuilder::new()
+            .weights(ParetoDistribution::new(1.0, 1.8))
+            .alpha(alpha)
+            .vertices(2400)
+            .seeds(&[21, 22, 23, 24])
+            .tile_size(1200)
+            .graph_kind(graph_kind)
+            .norm(norm)
+            .geometry(geometry)
+            .build()
+            .unwrap()
+    }
+
+    fn edges(params: &GenerationParameters<VecSeeds>, skip: bool) -> Vec<(u64, u64)> {
+        let nodes = NodeSource::new(params);
+        let mut edges = Vec::new();
+        for (start, end) in params.tiles() {
+            if skip {
+                skip_worker_function(start, end, params, &nodes, |i, j| edges.push((i, j)));
+            } else {
+                crate::worker_function(start, end, params, &nodes, |i, j| edges.push((i, j)));
+            }
+        }
+        edges.sort_unstable();
+        edges
+    }
+
+    #[test]
+    fn threshold_matches_per_pair() {
+        for graph_kind in [GraphKind::Directed, GraphKind::Undirected] {
+            for (norm, geometry) in [(Norm::Max, Geometry::Torus), (Norm::L2, Geometry::Cube)] {
+                let params = params(f32::INFINITY, graph_kind, norm, geometry);
+                let expected = edges(&params, false);
+                assert!(!expected.is_empty(), "expected some edges");
+                assert_eq!(
+                    edges(&params, true),
+                    expected,
+                    "{:?} {:?} {:?}",
+                    graph_kind,
+                    norm,
+                    geometry
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn soft_model_degrees_match_per_pair() {
+        let params = params(1.5, GraphKind::Undirected, Norm::Max, Geometry::Torus);
+        let degrees = |edges: &[(u64, u64)]| {
+            let mut degrees = vec![0u64; params.v as usize];
+            for (i, j) in edges {
+                degrees[*i as usize] += 1;
+                degrees[*j as usize] += 1;
+            }
+            degrees
+        };
+        let expected = degrees(&edges(&params, false));
+        let actual = degrees(&edges(&params, true));
+
+        let (expected_sum, actual_sum) = (
+            expected.iter().sum::<u64>() as f64,
+            actual.iter().sum::<u64>() as f64,
+        );
+        assert!(
+            (actual_sum / expected_sum - 1.0).abs() < 0.05,
+            "{} vs {}",
+            actual_sum,
+            expected_sum
+        );
+
+        // The heaviest nodes must keep their degree, not just the total.
+        let heavy: Vec<usize> = (0..params.v as usize)
+            .filter(|j| expected[*j] >= 40)
+            .collect();
+        assert!(!heavy.is_empty());
+        let (e, a) = heavy
+            .iter()
+            .fold((0u64, 0u64), |(e, a), j| (e + expected[*j], a + actual[*j]));
+        assert!((a as f64 / e as f64 - 1.0).abs() < 0.1, "{} vs {}", a, e);
+    }
+}
@@ -0,0 +1,323 @@
+//! CPU generator on a work-stealing thread pool.
+//!
+//! The [CPUGenerator](crate::CPUGenerator) workers each take whole tiles from the tile channel, so a tile with many edges (like one holding the hubs of the graph) keeps its worker busy while the others run out of tiles at the end.
+//! Here a single worker feeds the tiles into a [rayon] pool instead, and every tile is split recursively into quadrants while its estimated cost is high.
+//! The quadrants are generated with [rayon::join], so idle threads steal them from busy ones.
+//!
+//! The cost of a tile is the number of its pairs plus [PAIRS_PER_EDGE] times the expected number of its edges, estimated as `(sum w_i)(sum w_j) / W` from the weights of its rows and columns.
+//! Every quadrant is generated like a tile of the [CPUGenerator](crate::CPUGenerator) by [worker](crate::worker).
+//! The per-pair hash does not depend on the tiles, so the edges are the same as those of the [CPUGenerator](crate::CPUGenerator).
+//! Only with [skip sampling](GenerationParameters::skip_sampling) they differ, as its RNG is seeded per (sub-)tile, but they are still reproducible.
+//!
+//! A tile is reported as finished once all of its quadrants are done.
+
+use crate::{NodeSource, PregeneratedNodes};
+use crossbeam_channel::{Receiver, Sender};
+use generator_common::params::ext::GenerationParametersExt;
+use generator_common::params::{EdgeModel, GenerationParameters, GraphKind, VecSeeds};
+use std::ops::Range;
+use std::sync::Arc;
+use tracing::{info, instrument};
+
+/// The cost of emitting an edge, in the cost of testing a pair.
+pub const PAIRS_PER_EDGE: f64 = 16.0;
+
+/// Tiles with a cost above this are split into quadrants.
+pub const SPLIT_COST: f64 = (1u64 << 18) as f64;
+
+/// Sides of a tile shorter than twice this are not split.
+pub const MIN_SIDE: u64 = 32;
+
+/// The argument the [WorkStealingGenerator] workers are constructed with.
+#[derive(Clone)]
+pub struct WorkStealingArgument {
+    /// The table shared by the workers, if any, see [PregeneratedNodes::shared].
+    pub nodes: Option<Arc<PregeneratedNodes>>,
+    /// The pool the tiles are generated on.
+    pub pool: Arc<rayon::ThreadPool>,
+}
+
+impl WorkStealingArgument {
+    /// Creates a pool with the given number of threads, and computes the nodes up front if the parameters ask for pregenerated numbers.
+    pub fn new<M: EdgeModel>(
+        params: &GenerationParameters<VecSeeds, M>,
+        threads: usize,
+    ) -> anyhow::Result<Self> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|i| format!("girg-worker-{}", i))
+            .build()?;
+        Ok(Self {
+            nodes: PregeneratedNodes::shared(params),
+            pool: Arc::new(pool),
+        })
+    }
+}
+
+/// Generator that runs the tiles on a work-stealing pool, splitting heavy tiles. See the [module documentation](self).
+///
+/// It is meant to be started with a single worker, which only feeds the tiles into the pool.
+/// More workers share the pool of the [WorkStealingArgument] they are constructed with.
+pub struct WorkStealingGenerator {
+    /// The argument the generator was constructed with.
+    argument: WorkStealingArgument,
+}
+
+impl<M: EdgeModel + Send + Sync> generator_common::generator::GraphGenerator<M>
+    for WorkStealingGenerator
+{
+    type ConstructArgument = WorkStealingArgument;
+
+    fn new(argument: Self::ConstructArgument) -> anyhow::Result<Self> {
+        Ok(Self { argument })
+    }
+
+    #[instrument(skip_all)]
+    fn generate(
+        &self,
+        sender: Sender<Vec<(u64, u64)>>,
+        finisher: Sender<((u64, u64), (u64, u64))>,
+        receiver: Receiver<((u64, u64), (u64, u64))>,
+        params: &GenerationParameters<VecSeeds, M>,
+    ) -> anyhow::Result<()> {
+        params.validate()?;
+        info!("Running!");
+        let nodes = NodeSource::with_pregenerated(params, self.argument.nodes.clone())?;
+        let pool = &self.argument.pool;
+
+        // Limits the tiles in the pool, such that the tile channel still applies back pressure.
+        let (permits, returned) = crossbeam_channel::bounded(2 * pool.current_num_threads());
+        let nodes = &nodes;
+        pool.in_place_scope(|s| {
+            for (start, end) in receiver {
+                permits.send(()).unwrap();
+                let sender = sender.clone();
+                let finisher = finisher.clone();
+                let returned = returned.clone();
+                s.spawn(move |_| {
+                    info!("Job: {:?} -> {:?}", start, end);
+                    generate_tile(start, end, params, nodes, &sender);
+                    info!("Job done!");
+                    finisher.send((start, end)).unwrap();
+                    returned.recv().unwrap();
+                });
+            }
+        });
+        drop(sender);
+        drop(finisher);
+        info!("Thread exit.");
+        Ok(())
+    }
+}
+
+/// Prefix sums of the weights of a range of nodes.
+struct WeightSums {
+    /// The first node.
+    first: u64,
+    /// `sums[k]` is the sum of the weights of the first `k` nodes.
+    sums: Vec<f64>,
+}
+
+impl WeightSums {
+    /// Sums up the weights of the given nodes.
+    fn new<M: EdgeModel>(range: Range<u64>, nodes: &NodeSource<M>) -> Self {
+        let mut sums = Vec::with_capacity((range.end - range.start) as usize + 1);
+        sums.push(0.0f64);
+        for j in range.clone() {
+            sums.push(sums[sums.len() - 1] + nodes.weight(j) as f64);
+        }
+        Self {
+            first: range.start,
+            sums,
+        }
+    }
+
+    /// Sum of the weights of the given nodes.
+    fn sum(&self, range: &Range<u64>) -> f64 {
+        self.sums[(range.end - self.first) as usize]
+            - self.sums[(range.start - self.first) as usize]
+    }
+}
+
+/// Generates the edges of the tile from `start` to `end` and sends them, splitting it into quadrants while it is heavy.
+pub fn generate_tile<M: EdgeModel + Sync>(
+    start: (u64, u64),
+    end: (u64, u64),
+    params: &GenerationParameters<VecSeeds, M>,
+    nodes: &NodeSource<M>,
+    sender: &Sender<Vec<(u64, u64)>>,
+) {
+    let rows = start.0..end.0.min(params.v);
+    let columns = start.1..end.1.min(params.v);
+    if rows.is_empty() || columns.is_empty() {
+        return;
+    }
+    let row_weights = WeightSums::new(rows.clone(), nodes);
+    let column_weights = WeightSums::new(columns.clone(), nodes);
+    let cost = |rows: &Range<u64>, columns: &Range<u64>| {
+        let pairs = ((rows.end - rows.start) * (columns.end - columns.start)) as f64;
+        let edges = row_weights.sum(rows) * column_weights.sum(columns) / params.w as f64;
+        pairs + PAIRS_PER_EDGE * edges.min(pairs)
+    };
+    split(rows, columns, params.graph_kind, &cost, &|rows, columns| {
+        crate::worker(
+            sender.clone(),
+            (rows.start, columns.start),
+            (rows.end, columns.end),
+            params,
+            nodes,
+        )
+    });
+}
+
+/// Calls `f` for the sub-tiles of the tile with the given rows and columns, splitting it recursively into quadrants while its `cost` is above [SPLIT_COST].
+///
+/// The quadrants are processed with [rayon::join].
+/// Sub-tiles without candidate pairs (below the diagonal of undirected graphs) are left out.
+fn split<C, F>(rows: Range<u64>, columns: Range<u64>, graph_kind: GraphKind, cost: &C, f: &F)
+where
+    C: Fn(&Range<u64>, &Range<u64>) -> f64 + Sync,
+    F: Fn(Range<u64>, Range<u64>) + Sync,
+{
+    // Undirected graphs only have pairs with i < j.
+    if graph_kind == GraphKind::Undirected && rows.start + 1 >= columns.end {
+        return;
+    }
+    let rows_len = rows.end - rows.start;
+    let columns_len = columns.end - columns.start;
+    if rows_len < 2 * MIN_SIDE || columns_len < 2 * MIN_SIDE || cost(&rows, &columns) <= SPLIT_COST
+    {
+        f(rows, columns);
+        return;
+    }
+
+    let row_mid = rows.start + rows_len / 2;
+    let column_mid = columns.start + columns_len / 2;
+    let (top, bottom) = (rows.start..row_mid, row_mid..rows.end);
+    let (left, right) = (columns.start..column_mid, column_mid..columns.end);
+    rayon::join(
+        || {
+            rayon::join(
+                || split(top.clone(), left.clone(), graph_kind, cost, f),
+                || split(bottom.clone(), left.clone(), graph_kind, cost, f),
+            )
+        },
+        || {
+            rayon::join(
+                || split(top.clone(), right.clone(), graph_kind, cost, f),
+                || split(bottom.clone(), right.clone(), graph_kind, cost, f),
+            )
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generator_common::params::ext::GenerationParametersExt;
+    use generator_common::params::GenerationParametersBuilder;
+    use std::sync::Mutex;
+
+    fn params(graph_kind: GraphKind, skip_sampling: bool) -> GenerationParameters<VecSeeds> {
+        GenerationParametersBuilder::new()
+            .vertices(1200)
+            .seeds(&[7, 8, 9, 10])
+            .tile_size(700)
+            .graph_kind(graph_kind)
+            .skip_sampling(skip_sampling)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn sub_tiles_cover_the_candidate_pairs_once() {
+        let is_candidate =
+            |graph_kind: GraphKind, (i, j): (u64, u64)| graph_kind == GraphKind::Directed || i < j;
+        let cost = |rows: &Range<u64>, columns: &Range<u64>| {
+            4.0 * ((rows.end - rows.start) * (columns.end - columns.start)) as f64
+        };
+        for graph_kind in [GraphKind::Directed, GraphKind::Undirected] {
+            let pairs = Mutex::new(Vec::new());
+            let tiles = Mutex::new(0);
+            split(100..1100, 300..1000, graph_kind, &cost, &|rows, columns| {
+                let small = rows.end - rows.start < 2 * MIN_SIDE
+                    || columns.end - columns.start < 2 * MIN_SIDE;
+                assert!(small || cost(&rows, &columns) <= SPLIT_COST);
+                *tiles.lock().unwrap() += 1;
+                let mut pairs = pairs.lock().unwrap();
+                for j in columns {
+                    pairs.extend(rows.clone().map(|i| (i, j)));
+                }
+            });
+            assert!(tiles.into_inner().unwrap() > 1);
+
+            let mut pairs = pairs
+                .into_inner()
+                .unwrap()
+                .into_iter()
+                .filter(|p| is_candidate(graph_kind, *p))
+                .collect::<Vec<_>>();
+            pairs.sort_unstable();
+            let mut expected = (300..1000)
+                .flat_map(|j| (100..1100).map(move |i| (i, j)))
+                .filter(|p| is_candidate(graph_kind, *p))
+                .collect::<Vec<_>>();
+            expected.sort_unstable();
+            assert_eq!(pairs, expected, "{:?}", graph_kind);
+        }
+    }
+
+    #[test]
+    fn heavy_tiles_are_split() {
+        let params = params(GraphKind::Directed, false);
+        let nodes = NodeSource::new(&params);
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        generate_tile((0, 0), (700, 700), &params, &nodes, &sender);
+        drop(sender);
+        // Every sub-tile sends its own batches, a single 700x700 tile would send one batch per 1024 edges.
+        let batches = receiver.iter().collect::<Vec<_>>();
+        let edges: usize = batches.iter().map(|b| b.len()).sum();
+        assert!(batches.len() > (edges + 1023) / 1024);
+    }
+
+    #[test]
+    fn matches_cpu_generator() {
+        for graph_kind in [GraphKind::Directed, GraphKind::Undirected] {
+            let params = params(graph_kind, false);
+            let argument = WorkStealingArgument::new(&params, 3).unwrap();
+            let mut iter =
+                generator_common::generate_edges::<WorkStealingGenerator, _>(&params, argument, 1);
+            let mut edges = iter.by_ref().collect::<anyhow::Result<Vec<_>>>().unwrap();
+            edges.sort_unstable();
+            assert_eq!(iter.finished_tiles(), params.num_tiles());
+
+            let mut expected = generator_common::generate_edges::<crate::CPUGenerator, _>(
+                &params,
+                PregeneratedNodes::shared(&params),
+                2,
+            )
+            .collect::<anyhow::Result<Vec<_>>>()
+            .unwrap();
+            expected.sort_unstable();
+            assert!(!expected.is_empty());
+            assert_eq!(edges, expected, "{:?}", graph_kind);
+        }
+    }
+
+    #[test]
+    fn skip_sampling_is_reproducible() {
+        let params = params(GraphKind::Undirected, true);
+        let run = || {
+            let argument = WorkStealingArgument::new(&params, 2).unwrap();
+            let mut edges =
+                generator_common::generate_edges::<WorkStealingGenerator, _>(&params, argument, 1)
+                    .collect::<anyhow::Result<Vec<_>>>()
+                    .unwrap();
+            edges.sort_unstable();
+            edges
+        };
+        let edges = run();
+        assert!(!edges.is_empty());
+        assert_eq!(edges, run());
+    }
+}
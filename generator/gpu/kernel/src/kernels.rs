@@ -1,6 +1,6 @@
 use cuda_std::prelude::*;
 use generator_core::algorithm::generate_edge;
-use generator_core::params::{GenerationParameters, RawSeeds};
+use generator_core::params::{GenerationParameters, RandomMode, RawSeeds};
 use generator_core::MAX_DIMS;
 
 #[kernel]
@@ -23,8 +23,10 @@ pub unsafe fn generator_kernel(
     let mut p_i_prime = [0.0f32; MAX_DIMS];
     let mut p_j_prime = [0.0f32; MAX_DIMS];
 
+    // Tile caching only applies to the CPU generators, the kernel computes those numbers on demand.
+    let pregenerated = params.random_mode == RandomMode::PreGenerate;
     let w = |i: u64| {
-        if params.pregenerate_numbers {
+        if pregenerated {
             variables[(i as usize) * (params.num_dimensions() + 1)]
         } else {
             params.compute_weight(i)
@@ -41,26 +43,29 @@ pub unsafe fn generator_kernel(
     let mut j = ts.get_y();
 
     loop {
-        let ps_i: &[f32] = if params.pregenerate_numbers {
-            ps(i)
-        } else {
-            params.fill_dims(i, &mut p_i_prime[0..params.num_dimensions()]);
-            &p_i_prime[0..params.num_dimensions()]
-        };
-        let ps_j: &[f32] = if params.pregenerate_numbers {
-            ps(j)
-        } else {
-            params.fill_dims(j, &mut p_j_prime[0..params.num_dimensions()]);
-            &p_j_prime[0..params.num_dimensions()]
-        };
+        // Pairs that can never be an edge (i >= j for undirected graphs) are skipped early.
+        if params.is_candidate_pair(i, j) {
+            let ps_i: &[f32] = if pregenerated {
+                ps(i)
+            } else {
+                params.fill_dims(i, &mut p_i_prime[0..params.num_dimensions()]);
+                &p_i_prime[0..params.num_dimensions()]
+            };
+            let ps_j: &[f32] = if pregenerated {
+                ps(j)
+            } else {
+                params.fill_dims(j, &mut p_j_prime[0..params.num_dimensions()]);
+                &p_j_prime[0..params.num_dimensions()]
+            };
 
-        if generate_edge(i, j, w(i), w(j), ps_i, ps_j, params) {
-            if !ts.can_add_edge() {
-                // No more space in buffer, abort!
-                ts.set_done(false);
-                break;
+            if generate_edge(i, j, w(i), w(j), ps_i, ps_j, params) {
+                if !ts.can_add_edge() {
+                    // No more space in buffer, abort!
+                    ts.set_done(false);
+                    break;
+                }
+                ts.add_edge(i, j);
             }
-            ts.add_edge(i, j);
         }
 
         // Increment i,j
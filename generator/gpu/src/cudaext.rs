@@ -22,17 +22,25 @@ impl GenerationParametersCudaExt for GenerationParameters<VecSeeds> {
             .context("get_dbuffer_async")?;
         let params = GenerationParameters {
             seeds: RawSeeds::new(buffer.as_device_ptr().as_ptr()),
-            pregenerate_numbers: self.pregenerate_numbers,
+            random_mode: self.random_mode,
             gpu_blocks: self.gpu_blocks,
+            skip_sampling: self.skip_sampling,
             dims: self.dims,
-            pareto: self.pareto,
+            weights: self.weights,
             alpha: self.alpha,
+            c: self.c,
             w: self.w,
             v: self.v,
             tile_size: self.tile_size,
             edgebuffer_size: self.edgebuffer_size,
             shard_index: self.shard_index,
             shard_count: self.shard_count,
+            graph_kind: self.graph_kind,
+            edge_randomness: self.edge_randomness,
+            hash: self.hash,
+            norm: self.norm,
+            geometry: self.geometry,
+            model: self.model,
         };
 
         Ok((params, buffer))
@@ -7,7 +7,6 @@ use cust::error::CudaResult;
 use cust::memory::{DeviceBox, GpuBuffer};
 use cust::prelude::*;
 use generator_common::generator::GraphGenerator;
-use generator_common::MAX_DIMS;
 use once_cell::sync::OnceCell;
 use std::sync::Arc;
 use tracing::{debug, info, instrument, warn};
@@ -15,6 +14,7 @@ use tracing::{debug, info, instrument, warn};
 use crate::cudaext::GenerationParametersCudaExt;
 use crate::state::cpu::CPUThreadState;
 use generator_common::params::ext::GenerationParametersExt;
+use generator_common::params::validate::validate_gpu;
 use generator_common::params::{GenerationParameters, VecSeeds};
 
 mod cudaext;
@@ -44,10 +44,6 @@ impl GPUGenerator {
     ) -> anyhow::Result<()> {
         info!("Starting a run...");
 
-        if params.num_dimensions() > MAX_DIMS && !params.pregenerate_numbers {
-            panic!("On-Demand GPU Computation requires you have at most {} dimensions. If more are needed, adjust MAX_DIMS in generator/core/lib.rs at the cost of GPU performance.", MAX_DIMS);
-        }
-
         let kernel_function = self
             .module
             .get_function("generator_kernel")
@@ -137,6 +133,8 @@ impl GraphGenerator for GPUGenerator {
         receiver: Receiver<((u64, u64), (u64, u64))>,
         params: &GenerationParameters<VecSeeds>,
     ) -> anyhow::Result<()> {
+        validate_gpu(params)?;
+
         let stream = Stream::new(StreamFlags::NON_BLOCKING, None).context("new stream")?;
 
         let variables = params.compute_interleaved_variables();
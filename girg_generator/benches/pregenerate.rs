@@ -0,0 +1,96 @@
+//! Compares computing the pregenerated weights and positions once per tile with sharing them between all tiles,
+//! with computing only the nodes of each tile (`--random-mode tile-cached`) and with computing them for every pair (`--random-mode on-demand`).
+//! Every case runs on a single thread, like a run with one worker.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use generator_common::params::ext::GenerationParametersExt;
+use generator_common::params::{GenerationParametersBuilder, RandomMode};
+use generator_cpu::{worker_function, NodeSource, PregeneratedNodes};
+
+fn pregenerate_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cpu_pregenerate");
+    group.sample_size(10);
+
+    for vertices in [1_024u64, 4_096, 10_000, 16_384] {
+        let params = GenerationParametersBuilder::new()
+            .vertices(vertices)
+            .tile_size(256)
+            .random_mode(RandomMode::PreGenerate)
+            .seed(21)
+            .build()
+            .expect("benchmark parameters");
+        group.throughput(Throughput::Elements(vertices * vertices));
+
+        // What every tile did before the nodes were shared.
+        group.bench_with_input(
+            BenchmarkId::new("per_tile", vertices),
+            &params,
+            |b, params| {
+                b.iter(|| {
+                    let mut edges = 0u64;
+                    for (start, end) in params.tiles() {
+                        let nodes = NodeSource::new(params);
+                        worker_function(start, end, params, &nodes, |_, _| edges += 1);
+                    }
+                    black_box(edges)
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("shared", vertices),
+            &params,
+            |b, params| {
+                b.iter(|| {
+                    let nodes =
+                        NodeSource::with_pregenerated(params, PregeneratedNodes::shared(params))
+                            .expect("matching nodes");
+                    let mut edges = 0u64;
+                    for (start, end) in params.tiles() {
+                        worker_function(start, end, params, &nodes, |_, _| edges += 1);
+                    }
+                    black_box(edges)
+                })
+            },
+        );
+
+        let mut tile_cached = params.clone();
+        tile_cached.random_mode = RandomMode::TileCached;
+        group.bench_with_input(
+            BenchmarkId::new("tile_cached", vertices),
+            &tile_cached,
+            |b, params| {
+                b.iter(|| {
+                    let nodes = NodeSource::new(params);
+                    let mut edges = 0u64;
+                    for (start, end) in params.tiles() {
+                        let tile_nodes = nodes.for_tile(start, end).expect("tile cached nodes");
+                        worker_function(start, end, params, &tile_nodes, |_, _| edges += 1);
+                    }
+                    black_box(edges)
+                })
+            },
+        );
+
+        let mut on_demand = params.clone();
+        on_demand.random_mode = RandomMode::OnDemand;
+        group.bench_with_input(
+            BenchmarkId::new("on_demand", vertices),
+            &on_demand,
+            |b, params| {
+                b.iter(|| {
+                    let nodes = NodeSource::new(params);
+                    let mut edges = 0u64;
+                    for (start, end) in params.tiles() {
+                        worker_function(start, end, params, &nodes, |_, _| edges += 1);
+                    }
+                    black_box(edges)
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(pregenerate, pregenerate_benchmark);
+criterion_main!(pregenerate);
@@ -8,7 +8,9 @@ use criterion::{
 use once_cell::sync::Lazy;
 use strum::{EnumIter, IntoEnumIterator};
 
-use girg_generator::{pbar, run_app, Args, GeneratorMode, RandomMode};
+use generator_common::params::{GenerationParametersBuilder, RandomMode};
+use girg_generator::args::GeneratorMode;
+use girg_generator::{pbar, RunConfig};
 
 fn get_suggested_launch_configuration() -> anyhow::Result<(u32, u32)> {
     let _ctx = cust::context::Context::create_and_push(
@@ -30,7 +32,7 @@ fn run(
     device: Option<cust::device::Device>,
     blocks: Option<u32>,
 ) {
-    let ctx = device.map(|device| {
+    let _ctx = device.map(|device| {
         cust::context::Context::create_and_push(
             cust::context::ContextFlags::MAP_HOST | cust::context::ContextFlags::SCHED_AUTO,
             device,
@@ -38,35 +40,27 @@ fn run(
         .expect("create cuda context")
     });
 
-    let args = Args {
-        generator,
-        random_mode: match pregen {
-            true => RandomMode::PreGenerate,
-            false => RandomMode::OnDemand,
-        },
-        workers,
-        blocks,
-        tile_size,
-        vertices,
-        alpha: 1.5,
-        beta: 1.5,
-        x_min: 1.0,
-        dimensions: 2,
-        shard_count: 1,
-        shard_index: 0,
-        device: 0,
-        output_degrees_distribution: None,
-        output_degrees_csv: None,
-        output_degrees_txt: None,
-        output_edges_csv: None,
-        output_edges_parquet: None,
-        output_weights: None,
-        output_positions: None,
-        seeds: None,
-        edgebuffer_size: 10240,
-    };
+    let mut builder = GenerationParametersBuilder::new()
+        .vertices(vertices)
+        .tile_size(tile_size)
+        .random_mode(if pregen {
+            RandomMode::PreGenerate
+        } else {
+            RandomMode::OnDemand
+        })
+        .gpu_blocks(blocks.unwrap_or(0))
+        .edgebuffer_size(10240);
+    // The grid generator only supports threshold GIRGs.
+    if generator == GeneratorMode::Grid {
+        builder = builder.alpha(f32::INFINITY);
+    }
+    let params = builder.build().expect("benchmark parameters");
 
-    run_app(black_box(args), black_box(ctx)).unwrap();
+    let mut config = RunConfig::new(params, generator.into());
+    config.workers = workers;
+    config.device = device_index();
+    let summary = girg_generator::run(config).expect("benchmark run");
+    black_box(summary.edges);
 }
 
 #[derive(Debug, EnumIter)]
@@ -79,13 +73,17 @@ enum BenchmarkType {
 const SAMPLE_SIZE: usize = 10;
 const GPU_THREADS: usize = 1;
 
+fn device_index() -> u32 {
+    std::env::var("CUDA_DEVICE")
+        .unwrap_or_else(|_| "0".to_string())
+        .parse::<u32>()
+        .expect("parse device index")
+}
+
 static CUST_DEVICE: Lazy<cust::device::Device> = Lazy::new(|| {
     cust::init(cust::CudaFlags::empty()).expect("cuda init");
 
-    let device_id = std::env::var("CUDA_DEVICE")
-        .unwrap_or_else(|_| "0".to_string())
-        .parse::<u32>()
-        .expect("parse device index");
+    let device_id = device_index();
 
     let device = cust::device::Device::get_device(device_id).expect("get device");
 
@@ -108,6 +106,9 @@ fn create_criterion_benchmark(c: &mut Criterion, mode: GeneratorMode, ty: Benchm
 
     let mode_str = match mode {
         GeneratorMode::CPU => "cpu",
+        GeneratorMode::BKL => "bkl",
+        GeneratorMode::Grid => "grid",
+        GeneratorMode::WorkStealing => "work_stealing",
         GeneratorMode::GPU => "gpu",
     };
     let type_str = match ty {
@@ -139,11 +140,17 @@ fn create_criterion_benchmark(c: &mut Criterion, mode: GeneratorMode, ty: Benchm
         match ty {
             BenchmarkType::SIZE => {
                 let num_workers = match mode {
-                    GeneratorMode::CPU => num_cpus::get(),
+                    GeneratorMode::CPU
+                    | GeneratorMode::BKL
+                    | GeneratorMode::Grid
+                    | GeneratorMode::WorkStealing => num_cpus::get(),
                     GeneratorMode::GPU => GPU_THREADS,
                 };
                 let range_end = match mode {
-                    GeneratorMode::CPU => 18,
+                    GeneratorMode::CPU
+                    | GeneratorMode::BKL
+                    | GeneratorMode::Grid
+                    | GeneratorMode::WorkStealing => 18,
                     GeneratorMode::GPU => 20,
                 };
 
@@ -162,7 +169,10 @@ fn create_criterion_benchmark(c: &mut Criterion, mode: GeneratorMode, ty: Benchm
             }
 
             BenchmarkType::CORE => match mode {
-                GeneratorMode::CPU => {
+                GeneratorMode::CPU
+                | GeneratorMode::BKL
+                | GeneratorMode::Grid
+                | GeneratorMode::WorkStealing => {
                     let num_cores = num_cpus::get();
                     for cpus in 1..=num_cores {
                         group.throughput(Throughput::Elements(cpus as u64));
@@ -206,15 +216,24 @@ fn create_criterion_benchmark(c: &mut Criterion, mode: GeneratorMode, ty: Benchm
 
             BenchmarkType::TILE => {
                 let num_workers = match mode {
-                    GeneratorMode::CPU => num_cpus::get(),
+                    GeneratorMode::CPU
+                    | GeneratorMode::BKL
+                    | GeneratorMode::Grid
+                    | GeneratorMode::WorkStealing => num_cpus::get(),
                     GeneratorMode::GPU => GPU_THREADS,
                 };
                 let range_end = match mode {
-                    GeneratorMode::CPU => 16,
+                    GeneratorMode::CPU
+                    | GeneratorMode::BKL
+                    | GeneratorMode::Grid
+                    | GeneratorMode::WorkStealing => 16,
                     GeneratorMode::GPU => 13,
                 };
                 let vertices = match mode {
-                    GeneratorMode::CPU => 65536,
+                    GeneratorMode::CPU
+                    | GeneratorMode::BKL
+                    | GeneratorMode::Grid
+                    | GeneratorMode::WorkStealing => 65536,
                     GeneratorMode::GPU => 262144,
                 };
 
@@ -247,9 +266,15 @@ fn create_criterion_benchmark(c: &mut Criterion, mode: GeneratorMode, ty: Benchm
 fn criterion_benchmark(c: &mut Criterion) {
     pbar::setup_logging(Some("error".to_string()));
 
-    for gen_mode in [GeneratorMode::CPU, GeneratorMode::GPU] {
+    for gen_mode in [
+        GeneratorMode::CPU,
+        GeneratorMode::BKL,
+        GeneratorMode::Grid,
+        GeneratorMode::WorkStealing,
+        GeneratorMode::GPU,
+    ] {
         for bench_type in BenchmarkType::iter() {
-            girg_generator::benchmark::criterion_benchmark(c, gen_mode, bench_type);
+            create_criterion_benchmark(c, gen_mode, bench_type);
         }
     }
 }
@@ -1,35 +1,207 @@
-use clap::{ArgEnum, Parser, ValueHint};
-use generator_common::params::ext::GenerationParametersExt;
-use generator_common::params::{GenerationParameters, VecSeeds};
+use crate::node_files::{read_positions, read_weights};
+use crate::run::{Backend, OutputConfig, RunConfig};
+use anyhow::Context;
+use clap::{ArgEnum, ArgMatches, CommandFactory, FromArgMatches, Parser, ValueHint};
+use generator_common::params::{
+    GenerationParameters, GenerationParametersBuilder, HyperbolicParameters, NodeAttributes,
+    VecSeeds,
+};
 use generator_common::random::ParetoDistribution;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Arc;
 use strum::EnumIter;
 
 pub type ArgsRef = Arc<Args>;
 
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ArgEnum, Debug, EnumIter)]
+#[derive(
+    Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ArgEnum, Debug, Serialize, Deserialize, EnumIter,
+)]
 pub enum GeneratorMode {
+    #[serde(rename = "cpu")]
     CPU,
+    /// Linear expected-time sampler by Bringmann, Keusch and Lengler, runs on the CPU
+    #[serde(rename = "bkl")]
+    BKL,
+    /// Spatial grid sampler for threshold GIRGs (infinite alpha), runs on the CPU
+    #[serde(rename = "grid")]
+    Grid,
+    /// Like cpu, on a work-stealing pool of --workers threads that splits heavy tiles
+    #[serde(rename = "work-stealing")]
+    WorkStealing,
     #[cfg(feature = "gpu")]
+    #[serde(rename = "gpu")]
     GPU,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ArgEnum, Debug)]
+impl From<GeneratorMode> for Backend {
+    fn from(mode: GeneratorMode) -> Self {
+        match mode {
+            GeneratorMode::CPU => Self::CPU,
+            GeneratorMode::BKL => Self::BKL,
+            GeneratorMode::Grid => Self::Grid,
+            GeneratorMode::WorkStealing => Self::WorkStealing,
+            #[cfg(feature = "gpu")]
+            GeneratorMode::GPU => Self::GPU,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ArgEnum, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum RandomMode {
     PreGenerate,
     OnDemand,
+    /// Compute the nodes of the rows and columns of every tile once per tile (CPU generators, on demand for the GPU generator)
+    TileCached,
+}
+
+impl From<RandomMode> for generator_common::params::RandomMode {
+    fn from(mode: RandomMode) -> Self {
+        match mode {
+            RandomMode::PreGenerate => Self::PreGenerate,
+            RandomMode::OnDemand => Self::OnDemand,
+            RandomMode::TileCached => Self::TileCached,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ArgEnum, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GraphKind {
+    Directed,
+    Undirected,
+}
+
+impl From<GraphKind> for generator_common::params::GraphKind {
+    fn from(kind: GraphKind) -> Self {
+        match kind {
+            GraphKind::Directed => Self::Directed,
+            GraphKind::Undirected => Self::Undirected,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ArgEnum, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EdgeRandomness {
+    Bits32,
+    Bits64,
+}
+
+impl From<EdgeRandomness> for generator_common::params::EdgeRandomness {
+    fn from(r: EdgeRandomness) -> Self {
+        match r {
+            EdgeRandomness::Bits32 => Self::Bits32,
+            EdgeRandomness::Bits64 => Self::Bits64,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ArgEnum, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HashFunction {
+    Murmur3,
+    #[clap(name = "splitmix64")]
+    #[serde(rename = "splitmix64")]
+    SplitMix64,
+    Xxh3,
+    Philox,
+}
+
+impl From<HashFunction> for generator_common::params::HashFunction {
+    fn from(h: HashFunction) -> Self {
+        match h {
+            HashFunction::Murmur3 => Self::Murmur3,
+            HashFunction::SplitMix64 => Self::SplitMix64,
+            HashFunction::Xxh3 => Self::Xxh3,
+            HashFunction::Philox => Self::Philox,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ArgEnum, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Norm {
+    L1,
+    L2,
+    Lp,
+    Max,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ArgEnum, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Geometry {
+    Torus,
+    Cube,
+}
+
+impl From<Geometry> for generator_common::params::Geometry {
+    fn from(geometry: Geometry) -> Self {
+        match geometry {
+            Geometry::Torus => Self::Torus,
+            Geometry::Cube => Self::Cube,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ArgEnum, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WeightDistribution {
+    Pareto,
+    Constant,
+    LogNormal,
+    BoundedPareto,
+    DiscretePowerLaw,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ArgEnum, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Model {
+    Girg,
+    ChungLu,
+    Hyperbolic,
 }
 
 /// GIRG Generator
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone, Serialize, Deserialize)]
 pub struct Args {
     /// What generator to use
-    #[clap(short, long, arg_enum)]
+    #[clap(
+        short,
+        long,
+        arg_enum,
+        required = false,
+        required_unless_present_any = &["from-manifest", "config"]
+    )]
     pub generator: GeneratorMode,
     /// How to use the randomness
     #[clap(long, arg_enum, default_value_t = RandomMode::PreGenerate)]
     pub random_mode: RandomMode,
+    /// Skip over unlikely pairs with geometric jumps instead of testing every pair (not supported by the GPU generator). Same distribution, but different graphs for the same seeds
+    #[clap(long)]
+    pub skip_sampling: bool,
+    /// Kind of graph to generate. Undirected graphs only consider pairs i < j (no self-loops)
+    #[clap(long, arg_enum, default_value_t = GraphKind::Directed)]
+    pub graph_kind: GraphKind,
+    /// Bits of randomness per pair. With 32 bits probabilities below about 2.3e-10 are all treated alike, 64 bits honors probabilities down to 2^-64 but is slower
+    #[clap(long, arg_enum, default_value_t = EdgeRandomness::Bits32)]
+    pub edge_randomness: EdgeRandomness,
+    /// Hash family all random numbers are derived from. Each family yields a different graph for the same seeds
+    #[clap(long, arg_enum, default_value_t = HashFunction::Murmur3)]
+    pub hash: HashFunction,
+    /// Random graph model. Chung-Lu ignores the positions. The hyperbolic model requires a single dimension, which holds the angle of a node
+    #[clap(long, arg_enum, default_value_t = Model::Girg)]
+    pub model: Model,
+    /// Radius R of the hyperbolic disk. Defaults to 2 ln(vertices)
+    #[clap(long)]
+    pub hrg_radius: Option<f32>,
+    /// Curvature alpha_H of the hyperbolic model
+    #[clap(long, default_value_t = 0.75)]
+    pub hrg_alpha: f32,
+    /// Temperature T of the hyperbolic model. 0 yields the threshold model
+    #[clap(long, default_value_t = 0.0)]
+    pub hrg_temperature: f32,
     /// Number of worker threads
     #[clap(short, long, default_value_t = 1)]
     pub workers: usize,
@@ -41,13 +213,44 @@ pub struct Args {
     pub vertices: u64,
     /// Alpha value of the probability function
     #[clap(short, long, default_value_t = 1.5)]
+    #[serde(with = "generator_common::params::serde_float")]
     pub alpha: f32,
+    /// Distribution of the weights. Constant weights are x_min, the (bounded) pareto and discrete power law use x_min and beta
+    #[clap(long, arg_enum, default_value_t = WeightDistribution::Pareto)]
+    pub weight_distribution: WeightDistribution,
     /// Alpha value of the pareto distribution
     #[clap(short, long, default_value_t = 1.5)]
     pub beta: f32,
+    /// Power-law exponent tau of the degree distribution. Overrides beta with tau - 1
+    #[clap(long)]
+    pub degree_exponent: Option<f32>,
+    /// Scaling constant c of the probability function
+    #[clap(long, default_value_t = 1.0)]
+    pub scaling: f32,
+    /// Expected average degree. Overrides the scaling constant with one calibrated to reach this degree
+    #[clap(long)]
+    pub avg_degree: Option<f64>,
     /// x_min value of the pareto distribution
     #[clap(short, long, default_value_t = 1.0)]
     pub x_min: f32,
+    /// Upper bound of the weights when using `--weight-distribution bounded-pareto`
+    #[clap(long, default_value_t = 1000.0)]
+    pub x_max: f32,
+    /// Mean of the logarithm of the weights when using `--weight-distribution log-normal`
+    #[clap(long, default_value_t = 0.0)]
+    pub log_mu: f32,
+    /// Standard deviation of the logarithm of the weights when using `--weight-distribution log-normal`
+    #[clap(long, default_value_t = 1.0)]
+    pub log_sigma: f32,
+    /// Norm used to compute the distance between two positions
+    #[clap(long, arg_enum, default_value_t = Norm::Max)]
+    pub norm: Norm,
+    /// Exponent p of the norm when using `--norm lp`
+    #[clap(long, default_value_t = 2.0)]
+    pub norm_p: f32,
+    /// Space the nodes live in. The torus wraps around at the borders, the cube does not
+    #[clap(long, arg_enum, default_value_t = Geometry::Torus)]
+    pub geometry: Geometry,
     /// Number of spatial dimensions
     #[clap(short, long, default_value_t = 2)]
     pub dimensions: usize,
@@ -79,57 +282,210 @@ pub struct Args {
     /// File to write edges to (parquet: i, j) (recommended due to compression)
     pub output_edges_parquet: Option<PathBuf>,
     #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
-    /// File to write weights to (plain text, one weight per line)
+    /// File to write weights to (plain text, one weight per line). For the hyperbolic model these are the radii
     pub output_weights: Option<PathBuf>,
     #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
-    /// File to write position to (csv: one column per dimension)
+    /// File to write position to (csv: one column per dimension, preceded by a `# geometry=...` comment line)
     pub output_positions: Option<PathBuf>,
-    /// Seed values
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    /// File to read the weights from instead of generating them (same layout as the weights output, or parquet). Weights must be finite and positive. Sets the number of vertices
+    pub input_weights: Option<PathBuf>,
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    /// File to read the positions from instead of generating them (same layout as the positions output, or parquet). Coordinates must lie in [0, 1). Sets the number of vertices and dimensions
+    pub input_positions: Option<PathBuf>,
+    /// Master seed, the seeds for the weights, the edges and every dimension are derived from it. Random if neither this nor --seeds is given. The effective seeds are logged and recorded in the parquet metadata and the manifest
+    #[clap(long, conflicts_with = "seeds")]
+    pub seed: Option<u64>,
+    /// Seed values, one for the weights, one for the edges and one per dimension
     #[clap(long, short)]
     pub seeds: Option<Vec<u64>>,
     /// Size of the buffer used to hold edges before processing. Effectively edge batch size.
     #[clap(long, default_value_t = 1024)]
     pub edgebuffer_size: u64,
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    /// File to write the run manifest to (TOML if it ends in .toml, YAML if it ends in .yaml or .yml, JSON otherwise): the resolved arguments and parameters, the output files, the wall time and the host
+    pub output_manifest: Option<PathBuf>,
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    /// Replay the run recorded in this manifest. Arguments given on the command line override the recorded ones
+    #[serde(skip)]
+    pub from_manifest: Option<PathBuf>,
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    /// Read the arguments from this TOML, YAML (.yaml, .yml) or JSON (.json) file. Arguments given on the command line override the ones in the file
+    #[serde(skip)]
+    pub config: Option<PathBuf>,
+    /// Print the fully resolved configuration as TOML and exit
+    #[clap(long)]
+    #[serde(skip)]
+    pub print_config: bool,
 }
 
 impl Args {
-    pub fn new_ref() -> ArgsRef {
-        Arc::new(Self::parse())
+    pub fn new_ref() -> anyhow::Result<ArgsRef> {
+        let matches = Self::command().get_matches();
+        Ok(Arc::new(Self::from_matches(&matches)?))
+    }
+
+    /// Builds the arguments from the parsed command line, layered over `--from-manifest` and `--config` if given.
+    pub fn from_matches(matches: &ArgMatches) -> anyhow::Result<Self> {
+        if matches.is_present("from-manifest") || matches.is_present("config") {
+            crate::config::resolve_args(matches)
+        } else {
+            Ok(Self::from_arg_matches(matches)?)
+        }
+    }
+
+    pub fn get_beta(&self) -> f32 {
+        self.degree_exponent
+            .map(|tau| tau - 1.0)
+            .unwrap_or(self.beta)
     }
 
     pub fn get_pareto(&self) -> ParetoDistribution {
-        ParetoDistribution::new(self.x_min, self.beta)
-    }
-
-    pub fn get_params(&self) -> GenerationParameters<VecSeeds> {
-        match self.seeds.as_ref() {
-            None => GenerationParameters::new(
-                self.dimensions,
-                self.get_pareto(),
-                self.alpha,
-                self.vertices,
-                self.tile_size,
-                self.edgebuffer_size,
-                self.random_mode == RandomMode::PreGenerate,
-                self.blocks.unwrap_or(0),
-                self.shard_index,
-                self.shard_count,
-            ),
-            Some(s) => GenerationParameters::from_seeds(
-                self.dimensions,
-                self.get_pareto(),
-                self.alpha,
-                self.vertices,
-                s.as_slice(),
-                self.tile_size,
-                self.edgebuffer_size,
-                self.random_mode == RandomMode::PreGenerate,
-                self.blocks.unwrap_or(0),
-                self.shard_index,
-                self.shard_count,
-            ),
+        ParetoDistribution::new(self.x_min, self.get_beta())
+    }
+
+    pub fn get_weight_distribution(&self) -> generator_common::random::WeightDistribution {
+        use generator_common::random::WeightDistribution as D;
+        match self.weight_distribution {
+            WeightDistribution::Pareto => D::Pareto(self.get_pareto()),
+            WeightDistribution::Constant => D::Constant(self.x_min),
+            WeightDistribution::LogNormal => D::LogNormal {
+                mu: self.log_mu,
+                sigma: self.log_sigma,
+            },
+            WeightDistribution::BoundedPareto => D::BoundedPareto {
+                x_min: self.x_min,
+                x_max: self.x_max,
+                alpha: self.get_beta(),
+            },
+            WeightDistribution::DiscretePowerLaw => D::DiscretePowerLaw {
+                x_min: self.x_min,
+                alpha: self.get_beta(),
+            },
+        }
+    }
+
+    pub fn get_norm(&self) -> generator_common::params::Norm {
+        match self.norm {
+            Norm::L1 => generator_common::params::Norm::L1,
+            Norm::L2 => generator_common::params::Norm::L2,
+            Norm::Lp => generator_common::params::Norm::Lp(self.norm_p),
+            Norm::Max => generator_common::params::Norm::Max,
         }
     }
+
+    /// Loads the weights and positions given by `--input-weights` and `--input-positions`.
+    pub fn get_nodes(&self) -> anyhow::Result<Option<NodeAttributes>> {
+        if self.input_weights.is_none() && self.input_positions.is_none() {
+            return Ok(None);
+        }
+
+        let weights = self.input_weights.as_ref().map(read_weights).transpose()?;
+        let positions = self
+            .input_positions
+            .as_ref()
+            .map(read_positions)
+            .transpose()?;
+        Ok(Some(NodeAttributes::new(weights, positions)?))
+    }
+
+    /// The model given by `--model`, the default radius of the hyperbolic model depends on the number of vertices `v`.
+    pub fn get_model(&self, v: u64) -> anyhow::Result<generator_common::params::Model> {
+        Ok(match self.model {
+            Model::Girg => generator_common::params::Model::Girg,
+            Model::ChungLu => generator_common::params::Model::ChungLu,
+            Model::Hyperbolic => {
+                let radius = match self.hrg_radius {
+                    Some(radius) => radius,
+                    // 2 ln(v) is not positive for less than two vertices.
+                    None if v < 2 => anyhow::bail!(
+                        "The default radius of the hyperbolic model requires at least 2 vertices, got {}. Set --hrg-radius.",
+                        v
+                    ),
+                    None => 2.0 * (v as f32).ln(),
+                };
+                generator_common::params::Model::Hyperbolic(HyperbolicParameters {
+                    radius,
+                    alpha: self.hrg_alpha,
+                    temperature: self.hrg_temperature,
+                })
+            }
+        })
+    }
+
+    /// Builds the parameters of the graph, failing if the arguments are invalid, see [ValidationError](generator_common::params::ValidationError).
+    pub fn get_params(&self) -> anyhow::Result<GenerationParameters<VecSeeds>> {
+        // Checked here, the weight distribution only sees the derived beta = tau - 1.
+        if let Some(tau) = self.degree_exponent {
+            if tau.is_nan() || tau <= 1.0 {
+                anyhow::bail!("Degree exponent tau must be greater than 1, got {}.", tau);
+            }
+        }
+        let nodes = self
+            .get_nodes()
+            .context("Unable to load the node attributes.")?;
+        // Loaded attributes determine the number of vertices, which the hyperbolic model depends on.
+        let vertices = nodes
+            .as_ref()
+            .and_then(|n| n.num_nodes())
+            .map_or(self.vertices, |v| v as u64);
+
+        let mut builder = GenerationParametersBuilder::new()
+            .dimensions(self.dimensions)
+            .weights(self.get_weight_distribution())
+            .alpha(self.alpha)
+            .scaling(self.scaling)
+            .vertices(vertices)
+            .tile_size(self.tile_size)
+            .edgebuffer_size(self.edgebuffer_size)
+            .random_mode(self.random_mode.into())
+            .gpu_blocks(self.blocks.unwrap_or(0))
+            .skip_sampling(self.skip_sampling)
+            .shard(self.shard_index, self.shard_count)
+            .graph_kind(self.graph_kind.into())
+            .edge_randomness(self.edge_randomness.into())
+            .hash(self.hash.into())
+            .norm(self.get_norm())
+            .geometry(self.geometry.into())
+            .model(self.get_model(vertices)?);
+        match (self.seeds.as_ref(), self.seed) {
+            (Some(seeds), _) => builder = builder.seeds(seeds),
+            (None, Some(master)) => builder = builder.seed(master),
+            (None, None) => {}
+        }
+        if let Some(nodes) = nodes {
+            builder = builder.nodes(nodes);
+        }
+        if let Some(avg_degree) = self.avg_degree {
+            builder = builder.avg_degree(avg_degree);
+        }
+        builder.build()
+    }
+
+    /// The files to write.
+    pub fn get_output(&self) -> OutputConfig {
+        OutputConfig {
+            edges_csv: self.output_edges_csv.clone(),
+            edges_parquet: self.output_edges_parquet.clone(),
+            weights: self.output_weights.clone(),
+            positions: self.output_positions.clone(),
+            degrees_csv: self.output_degrees_csv.clone(),
+            degrees_txt: self.output_degrees_txt.clone(),
+            degrees_distribution: self.output_degrees_distribution.clone(),
+        }
+    }
+
+    /// The configuration of the run described by the arguments, see [run](crate::run()).
+    pub fn run_config(&self) -> anyhow::Result<RunConfig> {
+        Ok(RunConfig {
+            params: self.get_params()?,
+            generator: self.generator.into(),
+            workers: self.workers,
+            device: self.device,
+            output: self.get_output(),
+            progress: true,
+        })
+    }
 }
 
 #[cfg(feature = "gpu")]
@@ -138,3 +494,39 @@ impl generator_gpu::GPUGeneratorArguments for Args {
         self.device
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(argv: &[&str]) -> Args {
+        let matches = Args::command().get_matches_from(argv);
+        Args::from_matches(&matches).unwrap()
+    }
+
+    #[test]
+    fn hyperbolic_radius_needs_two_vertices() {
+        let hyperbolic = ["girg", "-g", "cpu", "--model", "hyperbolic", "-d", "1"];
+        for v in ["0", "1"] {
+            let e = args(&[&hyperbolic[..], &["-v", v]].concat())
+                .get_params()
+                .unwrap_err()
+                .to_string();
+            assert!(e.contains("--hrg-radius"), "{}", e);
+        }
+
+        let params = args(&[&hyperbolic[..], &["-v", "1", "--hrg-radius", "3"]].concat())
+            .get_params()
+            .unwrap();
+        assert_eq!(params.v, 1);
+    }
+
+    #[test]
+    fn degree_exponent_has_its_own_error() {
+        let e = args(&["girg", "-g", "cpu", "--degree-exponent", "0.5"])
+            .run_config()
+            .unwrap_err()
+            .to_string();
+        assert!(e.contains("Degree exponent tau"), "{}", e);
+    }
+}
@@ -1,17 +1,15 @@
 use girg_generator::args::Args;
+use girg_generator::config::{self, Format};
 use girg_generator::{pbar, run_app};
 use tracing::info;
 
 fn main() -> anyhow::Result<()> {
-    let app = Args::new_ref();
+    // Before the arguments are resolved, which may warn about the manifest. The log goes to stderr, so --print-config stays clean.
     pbar::setup_logging(None);
-
-    if app.shard_index >= app.shard_count {
-        panic!("Shard index must be less than shard count.");
-    }
-
-    if app.dimensions < 1 {
-        panic!("Number of dimensions must be at least 1.");
+    let app = Args::new_ref()?;
+    if app.print_config {
+        print!("{}", config::to_string(&*app, Format::Toml)?);
+        return Ok(());
     }
 
     info!("Running using the {:?} generator!", app.generator);
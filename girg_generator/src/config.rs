@@ -0,0 +1,305 @@
+//! Configuration files for [Args].
+//!
+//! A configuration file sets fields of [Args] by name, anything not set keeps its default:
+//!
+//! ```toml
+//! generator = "cpu"
+//! vertices = 100000
+//! random_mode = "on-demand"
+//! output_edges_parquet = "edges.parquet"
+//! ```
+//!
+//! Keys may also be written with dashes like the flags, and the values of the enums are the ones of the command line.
+//! The layers are applied in order: the defaults, the arguments of `--from-manifest`, the `--config` file and the flags given on the command line.
+//!
+//! Files are TOML, YAML or JSON depending on their extension, see [Format].
+//! TOML integers are signed 64-bit, so seeds above [i64::MAX] are written as strings in TOML files.
+
+use crate::args::Args;
+use crate::manifest::Manifest;
+use anyhow::Context;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// The format of a configuration or manifest file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// JSON, for files ending in `.json`.
+    Json,
+    /// TOML, for files ending in `.toml`.
+    Toml,
+    /// YAML, for files ending in `.yaml` or `.yml`.
+    Yaml,
+}
+
+impl Format {
+    /// The format of the file at `path` by its extension, [None] if the extension is not known.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "json" => Some(Self::Json),
+            "toml" => Some(Self::Toml),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// Makes a value representable in TOML.
+///
+/// Integers that do not fit TOML integers become strings, and fields without a value are dropped as TOML has no null.
+fn prepare_for_toml(value: &mut Value) {
+    match value {
+        Value::Number(n) if n.is_u64() && n.as_i64().is_none() => {
+            *value = Value::String(n.to_string());
+        }
+        Value::Array(a) => a.iter_mut().for_each(prepare_for_toml),
+        Value::Object(o) => {
+            *o = std::mem::take(o)
+                .into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .collect();
+            o.values_mut().for_each(prepare_for_toml);
+        }
+        _ => {}
+    }
+}
+
+/// Turns the large integers stored as strings by [prepare_for_toml] back into integers.
+fn strings_to_large_integers(value: &mut Value) {
+    match value {
+        Value::String(s) => {
+            if let Some(n) = s.parse::<u64>().ok().filter(|n| *n > i64::MAX as u64) {
+                *value = Value::from(n);
+            }
+        }
+        Value::Array(a) => a.iter_mut().for_each(strings_to_large_integers),
+        Value::Object(o) => o.values_mut().for_each(strings_to_large_integers),
+        _ => {}
+    }
+}
+
+/// Serializes `value` in the given format.
+pub fn to_string<T: Serialize>(value: &T, format: Format) -> anyhow::Result<String> {
+    match format {
+        Format::Json => Ok(serde_json::to_string_pretty(value)?),
+        Format::Toml => {
+            let mut value = serde_json::to_value(value)?;
+            prepare_for_toml(&mut value);
+            // Going through a toml::Value puts the tables after the plain values, as TOML requires.
+            let value = toml::Value::try_from(value).context("Unable to convert to TOML")?;
+            Ok(toml::to_string_pretty(&value)?)
+        }
+        Format::Yaml => Ok(serde_yaml::to_string(value)?),
+    }
+}
+
+/// Parses a value in the given format.
+pub fn from_str<T: DeserializeOwned>(s: &str, format: Format) -> anyhow::Result<T> {
+    match format {
+        Format::Json => Ok(serde_json::from_str(s)?),
+        Format::Toml => {
+            let value: toml::Value = toml::from_str(s)?;
+            let mut value = serde_json::to_value(value)?;
+            strings_to_large_integers(&mut value);
+            Ok(serde_json::from_value(value)?)
+        }
+        Format::Yaml => Ok(serde_yaml::from_str(s)?),
+    }
+}
+
+/// Reads the configuration file at `path`, defaulting to TOML for unknown extensions.
+///
+/// Dashes in the keys are replaced by underscores, such that they match the fields of [Args].
+pub fn read_config<P: AsRef<Path>>(path: P) -> anyhow::Result<Map<String, Value>> {
+    let path = path.as_ref();
+    let s = std::fs::read_to_string(path)
+        .with_context(|| format!("Unable to read {}", path.display()))?;
+    let value: Value = from_str(&s, Format::from_path(path).unwrap_or(Format::Toml))
+        .with_context(|| format!("Invalid configuration {}", path.display()))?;
+    match value {
+        Value::Object(o) => Ok(o
+            .into_iter()
+            .map(|(k, v)| (k.replace('-', "_"), v))
+            .collect()),
+        _ => anyhow::bail!("{}: expected a table of arguments.", path.display()),
+    }
+}
+
+/// The default arguments.
+///
+/// The generator has no default, [merge_args] checks that one is given.
+fn default_args() -> Args {
+    Args::try_parse_from(["girg_generator", "--generator", "cpu"]).expect("default arguments")
+}
+
+/// Sets a field of the serialized arguments.
+///
+/// The master seed and the seeds exclude each other, setting one clears the other.
+fn set_field(args: &mut Map<String, Value>, field: &str, value: Value) -> anyhow::Result<()> {
+    if !args.contains_key(field) {
+        anyhow::bail!("Unknown argument {}.", field);
+    }
+    if !value.is_null() {
+        match field {
+            "seed" => args.insert("seeds".to_string(), Value::Null),
+            "seeds" => args.insert("seed".to_string(), Value::Null),
+            _ => None,
+        };
+    }
+    args.insert(field.to_string(), value);
+    Ok(())
+}
+
+/// Applies the `layers` of serialized arguments in order on top of the defaults, followed by the arguments given explicitly in `matches`.
+///
+/// Defaults of the command line do not override the layers.
+/// The options naming the configuration files themselves are only taken from `matches`.
+pub fn merge_args(layers: &[Map<String, Value>], matches: &ArgMatches) -> anyhow::Result<Args> {
+    let defaults = default_args();
+    let mut cli = defaults.clone();
+    cli.update_from_arg_matches(matches)?;
+    let cli = serde_json::to_value(&cli)?;
+
+    let mut merged = match serde_json::to_value(&defaults)? {
+        Value::Object(o) => o,
+        _ => unreachable!("arguments serialize to a table"),
+    };
+    let mut has_generator = false;
+    for layer in layers {
+        for (field, value) in layer {
+            has_generator |= field == "generator" && !value.is_null();
+            set_field(&mut merged, field, value.clone())?;
+        }
+    }
+
+    for arg in Args::command().get_arguments() {
+        // The argument ids are the field names in kebab-case, `--help`, `--version` and the skipped fields have no value.
+        let field = arg.get_id().replace('-', "_");
+        let value = match cli.get(&field) {
+            Some(value) if matches.occurrences_of(arg.get_id()) > 0 => value.clone(),
+            _ => continue,
+        };
+        has_generator |= field == "generator";
+        set_field(&mut merged, &field, value)?;
+    }
+    if !has_generator {
+        anyhow::bail!("The generator must be given, with --generator or in the configuration.");
+    }
+
+    let mut args: Args = serde_json::from_value(Value::Object(merged))?;
+    args.from_manifest = matches.value_of_os("from-manifest").map(PathBuf::from);
+    args.config = matches.value_of_os("config").map(PathBuf::from);
+    args.print_config = matches.is_present("print-config");
+    Ok(args)
+}
+
+/// Builds the arguments from `--from-manifest`, `--config` and the command line, see [merge_args].
+pub fn resolve_args(matches: &ArgMatches) -> anyhow::Result<Args> {
+    let mut layers = Vec::new();
+
+    if let Some(p) = matches.value_of_os("from-manifest") {
+        let manifest = Manifest::read(p)?;
+        if manifest.version != env!("CARGO_PKG_VERSION") {
+            warn!(
+                "Manifest was written by version {}, this is version {}.",
+                manifest.version,
+                env!("CARGO_PKG_VERSION")
+            );
+        }
+        layers.push(manifest.args_layer()?);
+    }
+
+    if let Some(p) = matches.value_of_os("config") {
+        layers.push(read_config(p)?);
+    }
+
+    merge_args(&layers, matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(s: &str, format: Format) -> Map<String, Value> {
+        match from_str::<Value>(s, format).unwrap() {
+            Value::Object(o) => o
+                .into_iter()
+                .map(|(k, v)| (k.replace('-', "_"), v))
+                .collect(),
+            _ => panic!("not a table"),
+        }
+    }
+
+    #[test]
+    fn file_values_are_used_and_flags_override_them() {
+        let toml = config(
+            r#"
+            generator = "bkl"
+            vertices = 5000
+            alpha = "inf"
+            random-mode = "on-demand"
+            hash = "splitmix64"
+            output_edges_parquet = "edges.parquet"
+            seeds = [1, 2, 3, 4]
+            "#,
+            Format::Toml,
+        );
+        let yaml = config(
+            "generator: bkl\nvertices: 5000\nalpha: inf\nrandom_mode: on-demand\nhash: splitmix64\noutput-edges-parquet: edges.parquet\nseeds: [1, 2, 3, 4]\n",
+            Format::Yaml,
+        );
+        for layer in [toml, yaml] {
+            let matches = Args::command()
+                .get_matches_from(["girg", "--config", "run.toml", "-v", "10", "--seed", "3"]);
+            let args = merge_args(&[layer], &matches).unwrap();
+            assert_eq!(args.generator, crate::args::GeneratorMode::BKL);
+            assert_eq!(args.vertices, 10);
+            assert_eq!(args.alpha, f32::INFINITY);
+            assert_eq!(args.random_mode, crate::args::RandomMode::OnDemand);
+            assert_eq!(args.hash, crate::args::HashFunction::SplitMix64);
+            assert_eq!(
+                args.output_edges_parquet,
+                Some(PathBuf::from("edges.parquet"))
+            );
+            assert_eq!((args.seed, args.seeds), (Some(3), None));
+            assert_eq!(args.config, Some(PathBuf::from("run.toml")));
+            // Defaults stay in place.
+            assert_eq!(args.dimensions, 2);
+        }
+    }
+
+    #[test]
+    fn printed_config_reads_back() {
+        let matches = Args::command().get_matches_from([
+            "girg",
+            "-g",
+            "cpu",
+            "--seed",
+            "18446744073709551000",
+            "--output-weights",
+            "w.txt",
+        ]);
+        let args = Args::from_matches(&matches).unwrap();
+        for format in [Format::Toml, Format::Yaml, Format::Json] {
+            let layer = config(&to_string(&args, format).unwrap(), format);
+            let matches = Args::command().get_matches_from(["girg", "--config", "x"]);
+            let back = merge_args(&[layer], &matches).unwrap();
+            assert_eq!(back.seed, Some(18446744073709551000), "{:?}", format);
+            assert_eq!(back.output_weights, Some(PathBuf::from("w.txt")));
+        }
+    }
+
+    #[test]
+    fn unknown_keys_and_missing_generator_are_errors() {
+        let matches = Args::command().get_matches_from(["girg", "--config", "x"]);
+        let typo = config("generator = \"cpu\"\nvertice = 10", Format::Toml);
+        assert!(merge_args(&[typo], &matches).is_err());
+        let no_generator = config("vertices = 10", Format::Toml);
+        assert!(merge_args(&[no_generator], &matches).is_err());
+    }
+}
@@ -0,0 +1,228 @@
+//! Run manifests, which record everything needed to reproduce a run.
+//!
+//! A manifest holds the resolved [Args] (with the seeds that were actually used), the resulting [GenerationParameters],
+//! the output files with their sizes and edge counts, the wall time and some information about the host.
+//! Files ending in `.toml` or `.yaml` are written and read as TOML or YAML, all other files as JSON, see [Format].
+//! A manifest is replayed with `--from-manifest`, see [resolve_args](config::resolve_args).
+
+use crate::args::Args;
+use crate::config::{self, Format};
+use anyhow::Context;
+use generator_common::params::{GenerationParameters, VecSeeds};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+/// Everything recorded about a single run.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Manifest {
+    /// Version of the tool that performed the run.
+    pub version: String,
+    /// The arguments of the run, with the seeds resolved.
+    pub args: Args,
+    /// The parameters the graph was generated with.
+    pub params: GenerationParameters<VecSeeds>,
+    /// Number of edges generated.
+    pub edges: u64,
+    /// Wall time of the run in seconds.
+    pub wall_time_secs: f64,
+    /// The machine the run was performed on.
+    pub host: HostInfo,
+    /// The files written by the run.
+    pub outputs: Vec<OutputFile>,
+}
+
+/// A file written by a run.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OutputFile {
+    /// Path of the file.
+    pub path: PathBuf,
+    /// Size of the file in bytes.
+    pub bytes: u64,
+    /// Number of edges in the file, for edge files.
+    pub edges: Option<u64>,
+}
+
+impl OutputFile {
+    /// Describes the file at `path`, which must exist.
+    pub fn new<P: AsRef<Path>>(path: P, edges: Option<u64>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::metadata(path)
+            .with_context(|| format!("Unable to stat {}", path.display()))?
+            .len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            bytes,
+            edges,
+        })
+    }
+}
+
+/// The machine a run was performed on.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HostInfo {
+    /// Host name, if it can be determined.
+    pub hostname: Option<String>,
+    /// Operating system, see [std::env::consts::OS].
+    pub os: String,
+    /// CPU architecture, see [std::env::consts::ARCH].
+    pub arch: String,
+    /// Number of CPUs available to the process.
+    pub cpus: usize,
+}
+
+impl HostInfo {
+    /// Collects the information about the current machine.
+    pub fn current() -> Self {
+        let hostname = std::env::var("HOSTNAME")
+            .ok()
+            .or_else(|| std::fs::read_to_string("/proc/sys/kernel/hostname").ok())
+            .map(|h| h.trim().to_string())
+            .filter(|h| !h.is_empty());
+        Self {
+            hostname,
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            cpus: num_cpus::get(),
+        }
+    }
+}
+
+impl Manifest {
+    /// Serializes the manifest in the format given by the extension of `path`, see [Format::from_path].
+    pub fn to_string_for<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<String> {
+        config::to_string(self, Format::from_path(path).unwrap_or(Format::Json))
+    }
+
+    /// Parses a manifest written by [Manifest::to_string_for] for the same `path`.
+    pub fn from_str_for<P: AsRef<Path>>(s: &str, path: P) -> anyhow::Result<Self> {
+        config::from_str(s, Format::from_path(path).unwrap_or(Format::Json))
+    }
+
+    /// Writes the manifest to `path`.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_string_for(path)?)
+            .with_context(|| format!("Unable to write {}", path.display()))
+    }
+
+    /// Reads the manifest at `path`.
+    pub fn read<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let s = std::fs::read_to_string(path)
+            .with_context(|| format!("Unable to read {}", path.display()))?;
+        Self::from_str_for(&s, path).with_context(|| format!("Invalid manifest {}", path.display()))
+    }
+
+    /// The recorded arguments as a layer for [merge_args](config::merge_args).
+    ///
+    /// The output manifest is left out, such that replaying a run does not overwrite its manifest.
+    pub fn args_layer(&self) -> anyhow::Result<Map<String, Value>> {
+        let mut layer = match serde_json::to_value(&self.args)? {
+            Value::Object(o) => o,
+            _ => unreachable!("arguments serialize to a table"),
+        };
+        layer.remove("output_manifest");
+        Ok(layer)
+    }
+}
+
+/// The arguments of a run with the seeds that were actually used, such that running them again yields the same graph.
+pub fn resolved_args(args: &Args, params: &GenerationParameters<VecSeeds>) -> Args {
+    let mut args = args.clone();
+    match params.seeds.master {
+        Some(master) => {
+            args.seed = Some(master);
+            args.seeds = None;
+        }
+        None => {
+            args.seed = None;
+            args.seeds = Some(params.seeds.seeds.clone());
+        }
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::merge_args;
+    use clap::{CommandFactory, Parser};
+
+    fn manifest(args: &[&str]) -> Manifest {
+        let args = Args::parse_from(args);
+        let params = args.get_params().unwrap();
+        Manifest {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            args: resolved_args(&args, &params),
+            params,
+            edges: 12,
+            wall_time_secs: 0.5,
+            host: HostInfo::current(),
+            outputs: vec![OutputFile {
+                path: PathBuf::from("edges.parquet"),
+                bytes: 100,
+                edges: Some(12),
+            }],
+        }
+    }
+
+    #[test]
+    fn roundtrip_json_and_toml() {
+        let m = manifest(&[
+            "girg",
+            "-g",
+            "cpu",
+            "-v",
+            "100",
+            "-a",
+            "inf",
+            "--seed",
+            "18446744073709551000",
+        ]);
+        for name in ["run.json", "run.toml", "run.yaml"] {
+            let s = m.to_string_for(name).unwrap();
+            let back = Manifest::from_str_for(&s, name).unwrap();
+            assert_eq!(back.args.seed, Some(18446744073709551000), "{}", name);
+            assert_eq!(back.params.seeds.seeds, m.params.seeds.seeds, "{}", name);
+            assert_eq!(back.params.alpha, f32::INFINITY, "{}", name);
+            assert_eq!(back.params.w, m.params.w, "{}", name);
+            assert_eq!(back.outputs[0].edges, Some(12), "{}", name);
+        }
+    }
+
+    #[test]
+    fn replay_is_exact_with_overrides() {
+        let m = manifest(&["girg", "-g", "cpu", "-v", "100", "--shard-count", "4"]);
+
+        let matches = Args::command().get_matches_from(["girg", "--from-manifest", "run.json"]);
+        let replay = merge_args(&[m.args_layer().unwrap()], &matches).unwrap();
+        assert_eq!(
+            replay.get_params().unwrap().seeds.seeds,
+            m.params.seeds.seeds
+        );
+        assert_eq!((replay.vertices, replay.shard_count), (100, 4));
+        assert_eq!(replay.from_manifest, Some(PathBuf::from("run.json")));
+
+        let matches = Args::command().get_matches_from([
+            "girg",
+            "--from-manifest",
+            "run.json",
+            "--shard-index",
+            "2",
+            "-s",
+            "1",
+            "-s",
+            "2",
+            "-s",
+            "3",
+            "-s",
+            "4",
+        ]);
+        let replay = merge_args(&[m.args_layer().unwrap()], &matches).unwrap();
+        assert_eq!((replay.shard_index, replay.shard_count), (2, 4));
+        assert_eq!(replay.seed, None);
+        assert_eq!(replay.seeds, Some(vec![1, 2, 3, 4]));
+        assert_eq!(replay.vertices, 100);
+    }
+}
@@ -0,0 +1,126 @@
+//! Reading per-node weights and positions from files.
+//!
+//! The files use the same layout as the ones written by `--output-weights` and `--output-positions`:
+//! one row per node, with one column for the weight or one column per dimension for the position.
+//! Files ending in `.parquet` are read as Parquet, where every column must be a float or double column.
+//! All other files are read as CSV without a header, lines starting with `#` are skipped.
+
+use anyhow::Context;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::record::Field;
+use std::fs::File;
+use std::path::Path;
+
+/// Reads one weight per node.
+pub fn read_weights<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<f32>> {
+    let path = path.as_ref();
+    read_rows(path)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, row)| match row.as_slice() {
+            [w] => Ok(*w),
+            _ => anyhow::bail!(
+                "{}: row {} has {} columns, expected a single weight.",
+                path.display(),
+                i,
+                row.len()
+            ),
+        })
+        .collect()
+}
+
+/// Reads one position per node.
+pub fn read_positions<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<Vec<f32>>> {
+    read_rows(path.as_ref())
+}
+
+/// Reads all rows of the file as floats.
+fn read_rows(path: &Path) -> anyhow::Result<Vec<Vec<f32>>> {
+    let is_parquet = path
+        .extension()
+        .map_or(false, |e| e.eq_ignore_ascii_case("parquet"));
+    if is_parquet {
+        read_parquet_rows(path)
+    } else {
+        read_csv_rows(path)
+    }
+    .with_context(|| format!("Unable to read {}", path.display()))
+}
+
+/// Reads all rows of a CSV file without header, skipping `#` comment lines.
+fn read_csv_rows(path: &Path) -> anyhow::Result<Vec<Vec<f32>>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .comment(Some(b'#'))
+        .trim(csv::Trim::All)
+        .from_path(path)?;
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let row = record
+            .iter()
+            .map(|x| {
+                x.parse::<f32>()
+                    .with_context(|| format!("Invalid number {:?} in row {}", x, rows.len()))
+            })
+            .collect::<anyhow::Result<Vec<f32>>>()?;
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+/// Reads all rows of a Parquet file, every column must hold floats or doubles.
+fn read_parquet_rows(path: &Path) -> anyhow::Result<Vec<Vec<f32>>> {
+    let reader = SerializedFileReader::new(File::open(path)?)?;
+
+    let mut rows = Vec::new();
+    for row in reader.get_row_iter(None)? {
+        let row = row
+            .get_column_iter()
+            .map(|(name, field)| match field {
+                Field::Float(x) => Ok(*x),
+                Field::Double(x) => Ok(*x as f32),
+                _ => anyhow::bail!(
+                    "Column {} in row {} is not a float column.",
+                    name,
+                    rows.len()
+                ),
+            })
+            .collect::<anyhow::Result<Vec<f32>>>()?;
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn reads_output_layout() {
+        let dir = std::env::temp_dir().join(format!("girg_node_files_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let weights = dir.join("weights.txt");
+        let mut f = File::create(&weights).unwrap();
+        writeln!(f, "1.5\n2\n10.25").unwrap();
+        drop(f);
+        assert_eq!(read_weights(&weights).unwrap(), vec![1.5, 2.0, 10.25]);
+
+        let positions = dir.join("positions.csv");
+        let mut f = File::create(&positions).unwrap();
+        writeln!(f, "# geometry=torus\n0.1,0.2\n0.3,0.4\n0.5,0.6").unwrap();
+        drop(f);
+        assert_eq!(
+            read_positions(&positions).unwrap(),
+            vec![vec![0.1, 0.2], vec![0.3, 0.4], vec![0.5, 0.6]]
+        );
+        assert!(read_weights(&positions).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,87 +1,212 @@
+use anyhow::Context;
+use generator_common::params::{GenerationParameters, VecSeeds};
 use parquet::basic::Compression;
 use parquet::column::writer::ColumnWriter;
+use parquet::file::metadata::KeyValue;
 use parquet::file::properties::WriterProperties;
+use parquet::file::reader::{FileReader, SerializedFileReader};
 use parquet::file::writer::FileWriter;
 use parquet::file::writer::SerializedFileWriter;
 use parquet::schema::parser::parse_message_type;
 use std::fs::File;
 use std::path::Path;
 use std::sync::Arc;
-use tracing::info;
+use tracing::{info, warn};
 
+/// Footer key holding the [GenerationParameters] as JSON, including the seeds and the computed `w`.
+pub const PARAMS_KEY: &str = "girg_generator.params";
+/// Footer key holding the index of the shard in the file.
+pub const SHARD_INDEX_KEY: &str = "girg_generator.shard_index";
+/// Footer key holding the number of shards of the run.
+pub const SHARD_COUNT_KEY: &str = "girg_generator.shard_count";
+/// Footer key holding whether the nodes were loaded from files instead of generated from the seeds.
+pub const LOADED_NODES_KEY: &str = "girg_generator.loaded_nodes";
+/// Footer key holding the version of the tool that wrote the file.
+pub const VERSION_KEY: &str = "girg_generator.version";
+
+/// Builds the key/value metadata written into the footer of an edge file.
+pub fn edge_file_metadata(
+    params: &GenerationParameters<VecSeeds>,
+) -> anyhow::Result<Vec<KeyValue>> {
+    let json = serde_json::to_string(params).context("serialize generation parameters")?;
+    Ok(vec![
+        KeyValue::new(PARAMS_KEY.to_string(), json),
+        KeyValue::new(SHARD_INDEX_KEY.to_string(), params.shard_index.to_string()),
+        KeyValue::new(SHARD_COUNT_KEY.to_string(), params.shard_count.to_string()),
+        KeyValue::new(
+            LOADED_NODES_KEY.to_string(),
+            params.seeds.nodes.is_some().to_string(),
+        ),
+        KeyValue::new(
+            VERSION_KEY.to_string(),
+            env!("CARGO_PKG_VERSION").to_string(),
+        ),
+    ])
+}
+
+/// Rebuilds the [GenerationParameters] from the footer metadata of an edge file.
+///
+/// Node attributes that were loaded from files are not part of the metadata and have to be attached again with [with_nodes](generator_common::params::ext::GenerationParametersExt::with_nodes).
+pub fn params_from_metadata(
+    metadata: &[KeyValue],
+) -> anyhow::Result<GenerationParameters<VecSeeds>> {
+    let get = |key: &str| {
+        metadata
+            .iter()
+            .find(|kv| kv.key == key)
+            .and_then(|kv| kv.value.as_deref())
+    };
+
+    match get(VERSION_KEY) {
+        Some(version) if version != env!("CARGO_PKG_VERSION") => warn!(
+            "Edge file was written by version {}, this is version {}.",
+            version,
+            env!("CARGO_PKG_VERSION")
+        ),
+        None => warn!("Edge file does not record the version that wrote it."),
+        _ => {}
+    }
+    if get(LOADED_NODES_KEY) == Some("true") {
+        warn!(
+            "Edge file was generated from loaded node attributes, these have to be loaded again."
+        );
+    }
+
+    let json = get(PARAMS_KEY).context("edge file has no generation parameters in its metadata")?;
+    serde_json::from_str(json).context("parse generation parameters")
+}
+
+/// Reads the [GenerationParameters] an existing edge file was generated with, see [params_from_metadata].
+pub fn read_params<P: AsRef<Path>>(p: P) -> anyhow::Result<GenerationParameters<VecSeeds>> {
+    let file = File::open(p.as_ref()).context("open parquet file")?;
+    let reader = SerializedFileReader::new(file).context("read parquet file")?;
+    let metadata = reader
+        .metadata()
+        .file_metadata()
+        .key_value_metadata()
+        .as_deref()
+        .unwrap_or(&[]);
+    params_from_metadata(metadata)
+}
+
+/// Writes edges to a parquet file with the columns `i` and `j`, one row group per batch.
 pub struct ParquetEdgeWriter {
     writer: SerializedFileWriter<File>,
 }
 
 impl ParquetEdgeWriter {
-    pub fn new<P: AsRef<Path>>(p: P) -> Self {
+    /// Creates the file at `p`, recording the parameters in its footer, see [edge_file_metadata].
+    pub fn new<P: AsRef<Path>>(
+        p: P,
+        params: &GenerationParameters<VecSeeds>,
+    ) -> anyhow::Result<Self> {
         let message_type = "
   message edges_schema {
     REQUIRED INT64 i;
     REQUIRED INT64 j;
   }
 ";
-        let schema = Arc::new(parse_message_type(message_type).expect("parse schema"));
+        let schema = Arc::new(parse_message_type(message_type).context("parse schema")?);
         let props = Arc::new(
             WriterProperties::builder()
                 .set_statistics_enabled(false)
                 .set_compression(Compression::ZSTD)
+                .set_key_value_metadata(Some(
+                    edge_file_metadata(params).context("build parquet metadata")?,
+                ))
                 .build(),
         );
-        let file = std::fs::File::create(p.as_ref()).expect("create parquet output file");
-        let writer = SerializedFileWriter::new(file, schema, props).expect("create parquet writer");
-        Self { writer }
+        let file = std::fs::File::create(p.as_ref())
+            .with_context(|| format!("Unable to create {}", p.as_ref().display()))?;
+        let writer =
+            SerializedFileWriter::new(file, schema, props).context("create parquet writer")?;
+        Ok(Self { writer })
     }
 
-    pub fn write_vec(&mut self, v: &[(u64, u64)]) {
+    /// Writes the edges as a new row group.
+    pub fn write_vec(&mut self, v: &[(u64, u64)]) -> anyhow::Result<()> {
         let (i_vals, j_vals): (Vec<i64>, Vec<i64>) =
             v.iter().map(|(i, j)| ((*i) as i64, (*j) as i64)).unzip();
 
-        let mut row_group_writer = self.writer.next_row_group().expect("get row group writer");
-        let mut i_col = row_group_writer
-            .next_column()
-            .expect("next column")
-            .unwrap();
+        let mut row_group_writer = self
+            .writer
+            .next_row_group()
+            .context("get row group writer")?;
+        for (name, vals) in [("i", &i_vals), ("j", &j_vals)] {
+            let mut col = row_group_writer
+                .next_column()
+                .context("next column")?
+                .with_context(|| format!("schema has no column {}", name))?;
 
-        match i_col {
-            ColumnWriter::Int64ColumnWriter(ref mut typed_writer) => {
-                typed_writer
-                    .write_batch(&i_vals, None, None)
-                    .expect("writing i columns");
+            match col {
+                ColumnWriter::Int64ColumnWriter(ref mut typed_writer) => {
+                    typed_writer
+                        .write_batch(vals, None, None)
+                        .with_context(|| format!("writing {} columns", name))?;
+                }
+                _ => anyhow::bail!("Not designed to write non-edge columns."),
             }
-            _ => panic!("Not designed to write non-edge columns."),
-        }
 
-        row_group_writer
-            .close_column(i_col)
-            .expect("column i close");
-
-        let mut j_col = row_group_writer
-            .next_column()
-            .expect("next column")
-            .unwrap();
-
-        match j_col {
-            ColumnWriter::Int64ColumnWriter(ref mut typed_writer) => {
-                typed_writer
-                    .write_batch(&j_vals, None, None)
-                    .expect("writing i columns");
-            }
-            _ => panic!("Not designed to write non-edge columns."),
+            row_group_writer
+                .close_column(col)
+                .with_context(|| format!("column {} close", name))?;
         }
 
-        row_group_writer
-            .close_column(j_col)
-            .expect("column j close");
-
-        let rg_md = row_group_writer.close().expect("close rowgroupwriter");
+        let rg_md = row_group_writer.close().context("close rowgroupwriter")?;
         info!("Wrote {} edges to parquet file.", rg_md.num_rows());
         self.writer
             .close_row_group(row_group_writer)
-            .expect("close row group");
+            .context("close row group")?;
+        Ok(())
+    }
+
+    /// Writes the footer and closes the file.
+    pub fn close(&mut self) -> anyhow::Result<()> {
+        self.writer.close().context("close writer")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generator_common::params::GenerationParametersBuilder;
+    use generator_common::random::ParetoDistribution;
+
+    #[test]
+    fn metadata_roundtrip() {
+        let params = GenerationParametersBuilder::new()
+            .weights(ParetoDistribution::new(1.0, 2.5))
+            .alpha(f32::INFINITY)
+            .vertices(500)
+            .seed(42)
+            .tile_size(100)
+            .shard(1, 3)
+            .build()
+            .unwrap();
+
+        let metadata = edge_file_metadata(&params).unwrap();
+        let get = |key: &str| {
+            metadata
+                .iter()
+                .find(|kv| kv.key == key)
+                .and_then(|kv| kv.value.clone())
+        };
+        assert_eq!(get(SHARD_INDEX_KEY).as_deref(), Some("1"));
+        assert_eq!(get(SHARD_COUNT_KEY).as_deref(), Some("3"));
+        assert_eq!(get(VERSION_KEY).as_deref(), Some(env!("CARGO_PKG_VERSION")));
+
+        let back = params_from_metadata(&metadata).unwrap();
+        assert_eq!(back.seeds.seeds, params.seeds.seeds);
+        assert_eq!(back.seeds.master, Some(42));
+        assert_eq!(back.w, params.w);
+        assert_eq!(back.alpha, f32::INFINITY);
+        assert_eq!(back.v, 500);
+        assert_eq!((back.shard_index, back.shard_count), (1, 3));
     }
 
-    pub fn close(&mut self) {
-        self.writer.close().expect("close writer");
+    #[test]
+    fn missing_params_is_an_error() {
+        assert!(params_from_metadata(&[]).is_err());
     }
 }
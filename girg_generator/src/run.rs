@@ -0,0 +1,449 @@
+//! The library entry point, which generates a graph and writes the requested outputs.
+//!
+//! [run] only depends on a [RunConfig], so the generator can be embedded without going through the command line:
+//!
+//! ```no_run
+//! # use generator_common::params::GenerationParametersBuilder;
+//! # use girg_generator::{run, Backend, OutputConfig, RunConfig};
+//! let params = GenerationParametersBuilder::new()
+//!     .vertices(10_000)
+//!     .seed(42)
+//!     .build()?;
+//! let mut config = RunConfig::new(params, Backend::CPU);
+//! config.workers = 4;
+//! config.output = OutputConfig {
+//!     edges_parquet: Some("edges.parquet".into()),
+//!     ..OutputConfig::default()
+//! };
+//! let summary = run(config)?;
+//! println!("{} edges in {:?}", summary.edges, summary.timings.total);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use generator_common::edges::{self, EdgeIterator};
+use generator_common::params::ext::GenerationParametersExt;
+use generator_common::params::{GenerationParameters, Geometry, GraphKind, VecSeeds};
+use tracing::info;
+
+use crate::manifest::OutputFile;
+use crate::parquet_edges::ParquetEdgeWriter;
+use crate::{pbar, seeds_comment};
+
+/// The files a run writes, every file is optional.
+#[derive(Clone, Debug, Default)]
+pub struct OutputConfig {
+    /// Edges as csv: i, j.
+    pub edges_csv: Option<PathBuf>,
+    /// Edges as parquet: i, j.
+    pub edges_parquet: Option<PathBuf>,
+    /// Weights, plain text with one weight per line.
+    pub weights: Option<PathBuf>,
+    /// Positions, csv with one column per dimension.
+    pub positions: Option<PathBuf>,
+    /// Degrees as csv: node_id, degree.
+    pub degrees_csv: Option<PathBuf>,
+    /// Degrees, plain text with one degree per line.
+    pub degrees_txt: Option<PathBuf>,
+    /// Complementary cumulative degree distribution as csv.
+    pub degrees_distribution: Option<PathBuf>,
+}
+
+/// The generator that produces the edges of a run.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// See [CPUGenerator](generator_cpu::CPUGenerator).
+    CPU,
+    /// See [BKLGenerator](generator_cpu::bkl::BKLGenerator).
+    BKL,
+    /// See [GridGenerator](generator_cpu::grid::GridGenerator).
+    Grid,
+    /// See [WorkStealingGenerator](generator_cpu::stealing::WorkStealingGenerator).
+    WorkStealing,
+    /// See [GPUGenerator](generator_gpu::GPUGenerator).
+    #[cfg(feature = "gpu")]
+    GPU,
+}
+
+/// Everything needed for a run: the parameters of the graph, how to generate it and where to write it.
+#[derive(Clone, Debug)]
+pub struct RunConfig {
+    /// The parameters of the graph.
+    pub params: GenerationParameters<VecSeeds>,
+    /// The generator to use.
+    pub generator: Backend,
+    /// Number of worker threads.
+    pub workers: usize,
+    /// Index of the GPU used by the GPU generator.
+    pub device: u32,
+    /// The files to write.
+    pub output: OutputConfig,
+    /// Whether to show a progress bar of the finished tiles.
+    pub progress: bool,
+}
+
+impl RunConfig {
+    /// A run of a single worker without outputs or progress bar.
+    pub fn new(params: GenerationParameters<VecSeeds>, generator: Backend) -> Self {
+        Self {
+            params,
+            generator,
+            workers: 1,
+            device: 0,
+            output: OutputConfig::default(),
+            progress: false,
+        }
+    }
+}
+
+#[cfg(feature = "gpu")]
+impl generator_gpu::GPUGeneratorArguments for RunConfig {
+    fn get_device(&self) -> u32 {
+        self.device
+    }
+}
+
+/// Time spent in the phases of a run.
+#[derive(Clone, Debug, Default)]
+pub struct RunTimings {
+    /// Writing the weights and positions.
+    pub nodes: Duration,
+    /// Generating the edges, including writing the edge files.
+    pub edges: Duration,
+    /// Writing the degree files.
+    pub degrees: Duration,
+    /// The whole run.
+    pub total: Duration,
+}
+
+/// The result of a run.
+#[derive(Clone, Debug)]
+pub struct RunSummary {
+    /// The parameters the graph was generated with.
+    pub params: GenerationParameters<VecSeeds>,
+    /// Number of edges generated.
+    pub edges: u64,
+    /// Sum of all weights.
+    pub w: f32,
+    /// Time spent in the phases of the run.
+    pub timings: RunTimings,
+    /// The files written, in the order of the fields of [OutputConfig].
+    pub outputs: Vec<OutputFile>,
+}
+
+/// Writes the weights and positions requested by `output`.
+fn write_nodes(
+    params: &GenerationParameters<VecSeeds>,
+    output: &OutputConfig,
+) -> anyhow::Result<()> {
+    if let Some(p) = output.weights.as_ref() {
+        info!("Writing weights file...");
+        let mut f = File::create(p).with_context(|| format!("Unable to create {}", p.display()))?;
+        for i in params.compute_weights() {
+            writeln!(f, "{}", i)?;
+        }
+        f.flush()?;
+        info!("Done writing!");
+    }
+
+    if let Some(p) = output.positions.as_ref() {
+        info!("Writing positions file...");
+        let mut f = File::create(p).with_context(|| format!("Unable to create {}", p.display()))?;
+        // Record the geometry, positions on the torus and in the cube are not interchangeable.
+        let geometry = match params.geometry {
+            Geometry::Torus => "torus",
+            Geometry::Cube => "cube",
+        };
+        writeln!(f, "# geometry={}", geometry)?;
+        for i in params.compute_positions() {
+            for j in 0..i.len() {
+                write!(f, "{}", i[j])?;
+                if j < i.len() - 1 {
+                    write!(f, ",")?;
+                }
+            }
+            writeln!(f)?;
+        }
+        f.flush()?;
+        info!("Done writing!");
+    }
+
+    Ok(())
+}
+
+/// Starts the configured generator, see [generate_edges](generator_common::edges::generate_edges).
+fn start_generator(config: &RunConfig) -> anyhow::Result<EdgeIterator> {
+    let params = &config.params;
+    Ok(match config.generator {
+        #[cfg(feature = "gpu")]
+        Backend::GPU => edges::generate_edges::<generator_gpu::GPUGenerator, _>(
+            params,
+            std::sync::Arc::new(config.clone()),
+            config.workers,
+        ),
+        Backend::CPU => edges::generate_edges::<generator_cpu::CPUGenerator, _>(
+            params,
+            generator_cpu::PregeneratedNodes::shared(params),
+            config.workers,
+        ),
+        Backend::BKL => edges::generate_edges::<generator_cpu::bkl::BKLGenerator, _>(
+            params,
+            generator_cpu::PregeneratedNodes::shared(params),
+            config.workers,
+        ),
+        Backend::Grid => edges::generate_edges::<generator_cpu::grid::GridGenerator, _>(
+            params,
+            generator_cpu::PregeneratedNodes::shared(params),
+            config.workers,
+        ),
+        // A single worker feeds the tiles into the pool, which has the configured number of threads.
+        Backend::WorkStealing => {
+            edges::generate_edges::<generator_cpu::stealing::WorkStealingGenerator, _>(
+                params,
+                generator_cpu::stealing::WorkStealingArgument::new(params, config.workers)?,
+                1,
+            )
+        }
+    })
+}
+
+/// Generates the edges with the configured generator, writes the edge files and returns the number of edges and the degrees.
+///
+/// Errors of the workers are returned once the remaining workers stopped.
+fn generate_edges(config: &RunConfig) -> anyhow::Result<(u64, Vec<usize>)> {
+    let params = &config.params;
+    let output = &config.output;
+
+    if config.progress {
+        pbar::create_progress_bar(params.num_tiles());
+    }
+    pbar::increment_progress(0);
+    let result = receive_edges(params, output, start_generator(config)?);
+    if config.progress {
+        pbar::finish_progress_bar();
+    }
+
+    result
+}
+
+/// Receives all edges from the workers, writing the edge files and counting the degrees.
+fn receive_edges(
+    params: &GenerationParameters<VecSeeds>,
+    output: &OutputConfig,
+    edges: EdgeIterator,
+) -> anyhow::Result<(u64, Vec<usize>)> {
+    let mut edges = edges.on_finished_tile(|_| pbar::increment_progress(1));
+
+    let mut degree_counters: Vec<usize> = Vec::new();
+    degree_counters.resize(params.v as usize, 0usize);
+
+    info!("Receiving edges...");
+
+    let mut csv_wtr = output
+        .edges_csv
+        .as_ref()
+        .map(|p| -> anyhow::Result<_> {
+            let mut wtr = csv::Writer::from_path(p)?;
+            wtr.write_record(&["edge_i", "edge_j"])?;
+            Ok(wtr)
+        })
+        .transpose()?;
+
+    let mut parquet_wtr = output
+        .edges_parquet
+        .as_ref()
+        .map(|p| ParquetEdgeWriter::new(p, params))
+        .transpose()?;
+
+    let mut edge_counter = 0u64;
+    while let Some(edge_tile) = edges.next_batch() {
+        let edge_tile = edge_tile?;
+        if let Some(wtr) = parquet_wtr.as_mut() {
+            wtr.write_vec(&edge_tile)?;
+        }
+
+        for (i, j) in edge_tile {
+            edge_counter += 1;
+            *degree_counters.get_mut(i as usize).unwrap() += 1;
+            if params.graph_kind == GraphKind::Undirected {
+                *degree_counters.get_mut(j as usize).unwrap() += 1;
+            }
+            if let Some(wtr) = csv_wtr.as_mut() {
+                wtr.write_record(&[format!("{}", i), format!("{}", j)])?;
+            }
+        }
+    }
+
+    if let Some(wtr) = csv_wtr.as_mut() {
+        wtr.flush()?;
+    }
+
+    if let Some(wtr) = parquet_wtr.as_mut() {
+        wtr.close()?;
+    }
+
+    info!("All edges received! ({} edges)", edge_counter);
+
+    Ok((edge_counter, degree_counters))
+}
+
+/// Writes the degree files requested by `output`.
+fn write_degrees(degree_counters: &[usize], output: &OutputConfig) -> anyhow::Result<()> {
+    if let Some(p) = output.degrees_csv.as_ref() {
+        info!("Writing degree csv...");
+        let mut wtr = csv::Writer::from_path(p)?;
+        wtr.write_record(&["node_id", "degree"])?;
+        for (i, j) in degree_counters.iter().enumerate() {
+            wtr.write_record(&[format!("{}", i), format!("{}", *j)])?;
+        }
+        wtr.flush()?;
+        info!("Done writing!");
+    }
+
+    if let Some(p) = output.degrees_txt.as_ref() {
+        info!("Writing degree txt...");
+        let mut f = File::create(p).with_context(|| format!("Unable to create {}", p.display()))?;
+        for i in degree_counters.iter() {
+            writeln!(f, "{}", i)?;
+        }
+        f.flush()?;
+        info!("Done writing!");
+    }
+
+    if let Some(p) = output.degrees_distribution.as_ref() {
+        info!("Writing degree distribution csv...");
+        let mut wtr = csv::Writer::from_path(p)?;
+        wtr.write_record(&["x", "number of nodes with degree > x / number of nodes"])?;
+        for x in 0..=degree_counters.len() {
+            let s: f64 = degree_counters.iter().filter(|&d| *d > x as usize).count() as f64;
+            let v = s / (degree_counters.len() as f64);
+            let x = x as f64;
+            wtr.write_record(&[format!("{}", x), format!("{}", v)])?;
+        }
+        wtr.flush()?;
+        info!("Done writing!");
+    }
+
+    Ok(())
+}
+
+/// Checks the parameters against the constraints of the configured generator, so the run fails before any work is done.
+fn check_generator(config: &RunConfig) -> anyhow::Result<()> {
+    match config.generator {
+        #[cfg(feature = "gpu")]
+        Backend::GPU => generator_common::params::validate::validate_gpu(&config.params)?,
+        Backend::Grid => {
+            config.params.validate()?;
+            generator_cpu::grid::check_parameters(&config.params)?;
+        }
+        _ => config.params.validate()?,
+    }
+    Ok(())
+}
+
+/// Generates the graph described by `config` and writes the requested outputs.
+pub fn run(config: RunConfig) -> anyhow::Result<RunSummary> {
+    let start_time = Instant::now();
+    let mut timings = RunTimings::default();
+
+    info!("Params:\n{:#?}", config.params);
+    info!("Seeds: {}", seeds_comment(&config.params));
+    check_generator(&config)?;
+
+    let phase = Instant::now();
+    write_nodes(&config.params, &config.output)?;
+    timings.nodes = phase.elapsed();
+
+    let phase = Instant::now();
+    let (edges, degree_counters) = generate_edges(&config)?;
+    timings.edges = phase.elapsed();
+
+    let phase = Instant::now();
+    write_degrees(&degree_counters, &config.output)?;
+    timings.degrees = phase.elapsed();
+
+    let output = &config.output;
+    let files = [
+        (output.edges_csv.as_ref(), Some(edges)),
+        (output.edges_parquet.as_ref(), Some(edges)),
+        (output.weights.as_ref(), None),
+        (output.positions.as_ref(), None),
+        (output.degrees_csv.as_ref(), None),
+        (output.degrees_txt.as_ref(), None),
+        (output.degrees_distribution.as_ref(), None),
+    ];
+    let outputs = files
+        .iter()
+        .filter_map(|(p, edges)| p.map(|p| OutputFile::new(p, *edges)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    timings.total = start_time.elapsed();
+
+    Ok(RunSummary {
+        w: config.params.w,
+        params: config.params,
+        edges,
+        timings,
+        outputs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generator_common::params::GenerationParametersBuilder;
+
+    #[test]
+    fn summary_matches_outputs() {
+        let params = GenerationParametersBuilder::new()
+            .vertices(300)
+            .tile_size(64)
+            .seed(19)
+            .build()
+            .unwrap();
+        let dir = std::env::temp_dir().join(format!("girg_run_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut config = RunConfig::new(params, Backend::CPU);
+        config.workers = 2;
+        config.output.edges_csv = Some(dir.join("edges.csv"));
+        config.output.degrees_txt = Some(dir.join("degrees.txt"));
+        let summary = run(config).unwrap();
+
+        let lines = std::fs::read_to_string(dir.join("edges.csv"))
+            .unwrap()
+            .lines()
+            .count() as u64;
+        assert_eq!(summary.edges, lines - 1);
+        let degrees: u64 = std::fs::read_to_string(dir.join("degrees.txt"))
+            .unwrap()
+            .lines()
+            .map(|l| l.parse::<u64>().unwrap())
+            .sum();
+        assert_eq!(summary.edges, degrees);
+        assert_eq!(summary.w, summary.params.w);
+        assert_eq!(summary.outputs.len(), 2);
+        assert_eq!(summary.outputs[0].edges, Some(summary.edges));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn generator_errors_are_returned() {
+        // The grid generator only supports the threshold model.
+        let params = GenerationParametersBuilder::new()
+            .vertices(300)
+            .alpha(1.5)
+            .seed(19)
+            .build()
+            .unwrap();
+        let mut config = RunConfig::new(params, Backend::Grid);
+        config.workers = 2;
+        let e = run(config).unwrap_err().to_string();
+        assert!(e.contains("alpha"), "{}", e);
+    }
+}